@@ -0,0 +1,100 @@
+//! Derive macros for [rsvici], which are re-exported by the crate with the `derive` feature enabled by default.
+//!
+//! [rsvici]: https://docs.rs/rsvici
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, LitStr, Type};
+
+/// Implements `Command` for a request message, and `StreamCommand` if the event is given, from `#[vici(...)]` with the following arguments:
+///
+/// - `command = "..."`: the name of the command, which is required.
+/// - `response = Type`: the type of the response, which defaults to `()`.
+/// - `event = "..."`: the name of the event streamed for the command.
+/// - `item = Type`: the type of the messages of the event, which is required along with `event`.
+///
+/// See the `command` module of rsvici for an example.
+#[proc_macro_derive(ViciCommand, attributes(vici))]
+pub fn derive_vici_command(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// The arguments of `#[vici(...)]`.
+#[derive(Default)]
+struct Attributes {
+    command: Option<LitStr>,
+    response: Option<Type>,
+    event: Option<LitStr>,
+    item: Option<Type>,
+}
+
+impl Attributes {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut attributes = Self::default();
+
+        for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("vici")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("command") {
+                    set(&mut attributes.command, &meta, meta.value()?.parse()?)
+                } else if meta.path.is_ident("response") {
+                    set(&mut attributes.response, &meta, meta.value()?.parse()?)
+                } else if meta.path.is_ident("event") {
+                    set(&mut attributes.event, &meta, meta.value()?.parse()?)
+                } else if meta.path.is_ident("item") {
+                    set(&mut attributes.item, &meta, meta.value()?.parse()?)
+                } else {
+                    Err(meta.error("expected `command`, `response`, `event`, or `item`"))
+                }
+            })?;
+        }
+
+        Ok(attributes)
+    }
+}
+
+fn set<T>(slot: &mut Option<T>, meta: &syn::meta::ParseNestedMeta, value: T) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(meta.error("duplicate argument"));
+    }
+
+    *slot = Some(value);
+    Ok(())
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let attributes = Attributes::parse(&input)?;
+
+    let Some(command) = attributes.command else {
+        return Err(syn::Error::new_spanned(&input.ident, "missing `#[vici(command = \"...\")]`"));
+    };
+    let response = match attributes.response {
+        Some(response) => quote!(#response),
+        None => quote!(()),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut expanded = quote! {
+        impl #impl_generics ::rsvici::Command for #name #ty_generics #where_clause {
+            const NAME: &'static str = #command;
+            type Response = #response;
+        }
+    };
+
+    match (attributes.event, attributes.item) {
+        (Some(event), Some(item)) => expanded.extend(quote! {
+            impl #impl_generics ::rsvici::StreamCommand for #name #ty_generics #where_clause {
+                const EVENT: &'static str = #event;
+                type Item = #item;
+            }
+        }),
+        (Some(event), None) => return Err(syn::Error::new_spanned(event, "missing `item` for the event")),
+        (None, Some(item)) => return Err(syn::Error::new_spanned(item, "missing `event` for the item")),
+        (None, None) => {},
+    }
+
+    Ok(expanded)
+}
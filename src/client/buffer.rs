@@ -0,0 +1,388 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{pin, sync::Notify};
+
+use crate::error::{self, Error, ErrorCode};
+
+use super::packet::Packet;
+
+/// How the messages of a subscription are buffered until they are consumed.
+///
+/// The listener delivers the messages of every subscription and the responses to every request on the same connection. With [`Buffer::Block`], a
+/// subscriber that does not keep up holds back the whole connection once its buffer is full, while the other policies never wait for the subscriber
+/// and either drop messages or let the buffer grow instead.
+///
+/// Subscriptions that have dropped messages yield an error categorized as [`Category::Lagged`] before the next message, which carries the number of
+/// messages lost. The stream continues after the error.
+///
+/// [`Category::Lagged`]: crate::error::Category::Lagged
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Buffer {
+    /// Holds up to the given number of messages and waits for the subscriber to consume one when the buffer is full.
+    Block(usize),
+
+    /// Holds up to the given number of messages and drops the oldest one to make room for a new one when the buffer is full.
+    DropOldest(usize),
+
+    /// Holds up to the given number of messages and drops new ones when the buffer is full.
+    DropNewest(usize),
+
+    /// Holds every message until it is consumed.
+    Unbounded,
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Self::Block(1)
+    }
+}
+
+/// Creates a channel between the listener and a caller, which buffers messages according to `buffer`.
+pub(crate) fn channel(buffer: Buffer) -> (Sender, Receiver) {
+    let shared = Arc::new(Shared {
+        buffer,
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            buffered: 0,
+            lost: 0,
+            senders: 1,
+            receiving: true,
+        }),
+        readable: Notify::new(),
+        writable: Notify::new(),
+    });
+
+    (Sender { shared: shared.clone() }, Receiver { shared })
+}
+
+struct Shared {
+    buffer: Buffer,
+    state: Mutex<State>,
+    readable: Notify,
+    writable: Notify,
+}
+
+struct State {
+    queue: VecDeque<Entry>,
+
+    /// The number of entries subject to the policy of the channel.
+    buffered: usize,
+
+    /// The number of messages dropped after the last entry.
+    lost: u64,
+
+    senders: usize,
+    receiving: bool,
+}
+
+struct Entry {
+    /// The number of messages dropped right before this entry.
+    lost: u64,
+
+    /// Whether this entry is subject to the policy of the channel. Confirmations and final errors are never dropped.
+    buffered: bool,
+
+    item: error::Result<Packet>,
+}
+
+impl State {
+    fn push(&mut self, item: error::Result<Packet>, buffered: bool) {
+        let lost = std::mem::take(&mut self.lost);
+        self.buffered += usize::from(buffered);
+        self.queue.push_back(Entry { lost, buffered, item });
+    }
+
+    fn drop_oldest(&mut self) {
+        let Some(index) = self.queue.iter().position(|entry| entry.buffered) else {
+            return;
+        };
+
+        let entry = self.queue.remove(index).unwrap();
+        self.buffered -= 1;
+
+        match self.queue.get_mut(index) {
+            Some(next) => next.lost += entry.lost + 1,
+            None => self.lost += entry.lost + 1,
+        }
+    }
+}
+
+/// The sending half of a channel, which is held by the listener.
+pub(crate) struct Sender {
+    shared: Arc<Shared>,
+}
+
+/// The receiving half of a channel, which is held by a caller.
+pub(crate) struct Receiver {
+    shared: Arc<Shared>,
+}
+
+/// The receiver has already been dropped.
+#[derive(Debug)]
+pub(crate) struct SendError;
+
+impl Sender {
+    /// Buffers the message according to the policy of the channel, waiting for space only with [`Buffer::Block`].
+    pub async fn send(&self, item: error::Result<Packet>) -> Result<(), SendError> {
+        loop {
+            let writable = self.shared.writable.notified();
+            pin!(writable);
+            writable.as_mut().enable();
+
+            {
+                let mut state = self.shared.state.lock().unwrap();
+                if !state.receiving {
+                    return Err(SendError);
+                }
+
+                match self.shared.buffer {
+                    Buffer::Block(capacity) if state.buffered >= capacity.max(1) => {},
+                    Buffer::DropNewest(capacity) if state.buffered >= capacity.max(1) => {
+                        state.lost += 1;
+                        return Ok(());
+                    },
+                    Buffer::DropOldest(capacity) => {
+                        while state.buffered >= capacity.max(1) {
+                            state.drop_oldest();
+                        }
+                        state.push(item, true);
+                        self.shared.readable.notify_one();
+                        return Ok(());
+                    },
+                    Buffer::Block(_) | Buffer::DropNewest(_) | Buffer::Unbounded => {
+                        state.push(item, true);
+                        self.shared.readable.notify_one();
+                        return Ok(());
+                    },
+                }
+            }
+
+            writable.await;
+        }
+    }
+
+    /// Buffers the message regardless of the policy of the channel, without waiting for space. This is used to deliver confirmations and errors that
+    /// must not be dropped.
+    pub fn force_send(&self, item: error::Result<Packet>) -> Result<(), SendError> {
+        let mut state = self.shared.state.lock().unwrap();
+        if !state.receiving {
+            return Err(SendError);
+        }
+
+        state.push(item, false);
+        self.shared.readable.notify_one();
+        Ok(())
+    }
+
+    /// Returns true if the receiver has already been dropped.
+    pub fn is_closed(&self) -> bool {
+        !self.shared.state.lock().unwrap().receiving
+    }
+
+    /// Waits until the receiver is dropped.
+    pub async fn closed(&self) {
+        loop {
+            let writable = self.shared.writable.notified();
+            pin!(writable);
+            writable.as_mut().enable();
+
+            if self.is_closed() {
+                return;
+            }
+
+            writable.await;
+        }
+    }
+
+    /// Returns true if both senders belong to the same channel.
+    pub fn same_channel(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+impl Clone for Sender {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().unwrap().senders += 1;
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.senders -= 1;
+        if state.senders == 0 {
+            self.shared.readable.notify_one();
+        }
+    }
+}
+
+impl Receiver {
+    /// Receives the next message, or an error categorized as [`Category::Lagged`] if messages have been dropped before it. Returns `None` once every
+    /// sender has been dropped and the buffer is drained.
+    ///
+    /// [`Category::Lagged`]: crate::error::Category::Lagged
+    pub async fn recv(&mut self) -> Option<error::Result<Packet>> {
+        loop {
+            {
+                let mut state = self.shared.state.lock().unwrap();
+                if let Some(entry) = state.queue.front_mut() {
+                    if entry.lost > 0 {
+                        return Some(Err(Error::data(ErrorCode::Lagged(std::mem::take(&mut entry.lost)))));
+                    }
+
+                    let entry = state.queue.pop_front().unwrap();
+                    state.buffered -= usize::from(entry.buffered);
+                    self.shared.writable.notify_waiters();
+                    return Some(entry.item);
+                }
+
+                if state.lost > 0 {
+                    return Some(Err(Error::data(ErrorCode::Lagged(std::mem::take(&mut state.lost)))));
+                }
+
+                if state.senders == 0 {
+                    return None;
+                }
+            }
+
+            self.shared.readable.notified().await;
+        }
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receiving = false;
+        state.queue.clear();
+        state.buffered = 0;
+        self.shared.writable.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio_test::{assert_pending, assert_ready, task};
+
+    use super::*;
+    use crate::client::packet_type::PacketType;
+
+    fn packet(n: u8) -> error::Result<Packet> {
+        Ok(Packet::new(PacketType::CmdResponse, vec![n]))
+    }
+
+    async fn recv(rx: &mut Receiver) -> Result<u8, u64> {
+        match rx.recv().await.expect("channel has been closed") {
+            Ok(packet) => Ok(packet.payload()[0]),
+            Err(e) => Err(e.lost_messages().expect("error is not lagged")),
+        }
+    }
+
+    #[tokio::test]
+    async fn block_waits_for_space() {
+        let (tx, mut rx) = channel(Buffer::Block(2));
+        assert!(tx.send(packet(1)).await.is_ok());
+        assert!(tx.send(packet(2)).await.is_ok());
+
+        let mut send = task::spawn(tx.send(packet(3)));
+        assert_pending!(send.poll());
+
+        assert_eq!(recv(&mut rx).await, Ok(1));
+        assert!(send.is_woken());
+        assert!(assert_ready!(send.poll()).is_ok());
+
+        assert_eq!(recv(&mut rx).await, Ok(2));
+        assert_eq!(recv(&mut rx).await, Ok(3));
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_counts_lost_messages() {
+        let (tx, mut rx) = channel(Buffer::DropOldest(2));
+        for n in 1..=4 {
+            assert!(tx.send(packet(n)).await.is_ok());
+        }
+
+        assert_eq!(recv(&mut rx).await, Err(2));
+        assert_eq!(recv(&mut rx).await, Ok(3));
+        assert_eq!(recv(&mut rx).await, Ok(4));
+    }
+
+    #[tokio::test]
+    async fn drop_newest_counts_lost_messages() {
+        let (tx, mut rx) = channel(Buffer::DropNewest(2));
+        for n in 1..=3 {
+            assert!(tx.send(packet(n)).await.is_ok());
+        }
+
+        assert_eq!(recv(&mut rx).await, Ok(1));
+        assert!(tx.send(packet(4)).await.is_ok());
+        assert!(tx.send(packet(5)).await.is_ok());
+
+        assert_eq!(recv(&mut rx).await, Ok(2));
+        assert_eq!(recv(&mut rx).await, Err(1));
+        assert_eq!(recv(&mut rx).await, Ok(4));
+        assert_eq!(recv(&mut rx).await, Err(1));
+    }
+
+    #[tokio::test]
+    async fn lag_is_reported_on_empty_queue() {
+        let (tx, mut rx) = channel(Buffer::DropNewest(1));
+        assert!(tx.send(packet(1)).await.is_ok());
+        assert!(tx.send(packet(2)).await.is_ok());
+
+        assert_eq!(recv(&mut rx).await, Ok(1));
+        assert_eq!(recv(&mut rx).await, Err(1));
+
+        let mut next = task::spawn(rx.recv());
+        assert_pending!(next.poll());
+    }
+
+    #[tokio::test]
+    async fn unbounded_holds_every_message() {
+        let (tx, mut rx) = channel(Buffer::Unbounded);
+        for n in 0..=u8::MAX {
+            assert!(tx.send(packet(n)).await.is_ok());
+        }
+        drop(tx);
+
+        for n in 0..=u8::MAX {
+            assert_eq!(recv(&mut rx).await, Ok(n));
+        }
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn force_send_ignores_policy() {
+        let (tx, mut rx) = channel(Buffer::DropNewest(1));
+        assert!(tx.send(packet(1)).await.is_ok());
+        assert!(tx.force_send(packet(2)).is_ok());
+        assert!(tx.send(packet(3)).await.is_ok());
+
+        assert_eq!(recv(&mut rx).await, Ok(1));
+        assert_eq!(recv(&mut rx).await, Ok(2));
+        assert_eq!(recv(&mut rx).await, Err(1));
+    }
+
+    #[tokio::test]
+    async fn closed_wakes_after_receiver_dropped() {
+        let (tx, rx) = channel(Buffer::Block(1));
+        assert!(tx.send(packet(1)).await.is_ok());
+
+        let mut closed = task::spawn(tx.closed());
+        let mut send = task::spawn(tx.send(packet(2)));
+        assert_pending!(closed.poll());
+        assert_pending!(send.poll());
+
+        drop(rx);
+        assert!(closed.is_woken());
+        assert!(send.is_woken());
+        assert_ready!(closed.poll());
+        assert!(assert_ready!(send.poll()).is_err());
+        assert!(tx.is_closed());
+    }
+}
@@ -0,0 +1,170 @@
+use std::io;
+
+use bytes::{Buf, Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::{packet::Packet, packet_type::PacketType, ClientOptions};
+use crate::error::{self, Error, ErrorCode};
+
+/// A codec for [`tokio_util::codec`] that frames packets of the VICI protocol with their length prefix.
+///
+/// Packets are decoded incrementally as their bytes arrive. A packet larger than the maximum frame size fails with an error categorized as
+/// [`Category::Protocol`] as soon as its length prefix is read, without its bytes being buffered.
+///
+/// # Example
+/// ```no_run
+/// use std::error::Error;
+///
+/// use futures_util::stream::TryStreamExt;
+/// use rsvici::raw::ViciCodec;
+/// use tokio::net::TcpListener;
+/// use tokio_util::codec::FramedRead;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn Error>> {
+///     let listener = TcpListener::bind("127.0.0.1:4502").await?;
+///     let (stream, _) = listener.accept().await?;
+///
+///     let mut packets = FramedRead::new(stream, ViciCodec::new());
+///     while let Some(packet) = packets.try_next().await? {
+///         println!("{}: {:?}", packet.packet_type(), packet.payload());
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`Category::Protocol`]: crate::error::Category::Protocol
+#[derive(Clone, Debug)]
+pub struct ViciCodec {
+    max_frame_size: usize,
+    skipping: usize,
+}
+
+/// A packet decoded by [`ViciCodec`] before its type and name are parsed.
+pub(crate) enum Frame {
+    /// The bytes of a packet.
+    Complete(BytesMut),
+
+    /// A packet that exceeds the maximum frame size, which is skipped except for its type and name.
+    Oversized { tag: u8, name: Option<Bytes>, len: usize },
+}
+
+impl ViciCodec {
+    /// Creates a codec with the default maximum frame size of [`ClientOptions`].
+    pub fn new() -> Self {
+        Self::with_max_frame_size(ClientOptions::default().max_frame_size)
+    }
+
+    /// Creates a codec that rejects packets larger than `max_frame_size` bytes.
+    pub fn with_max_frame_size(max_frame_size: usize) -> Self {
+        Self { max_frame_size, skipping: 0 }
+    }
+
+    /// Returns the maximum size in bytes of a packet to decode.
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Decodes the bytes of the next packet. The bytes of an oversized packet are discarded as they arrive, keeping the buffer at the beginning of
+    /// the next packet.
+    pub(crate) fn decode_frame(&mut self, src: &mut BytesMut) -> io::Result<Option<Frame>> {
+        if self.skipping > 0 {
+            let len = self.skipping.min(src.len());
+            src.advance(len);
+            self.skipping -= len;
+
+            if self.skipping > 0 {
+                return Ok(None);
+            }
+        }
+
+        let Some(prefix) = src.first_chunk::<4>() else {
+            src.reserve(4);
+            return Ok(None);
+        };
+
+        let len = u32::from_be_bytes(*prefix) as usize;
+        if len > self.max_frame_size {
+            let Some(&tag) = src.get(4) else {
+                return Ok(None);
+            };
+
+            // The name tells the listener who is waiting for the packet, and is short enough to be buffered.
+            let named = len > 1 && PacketType::from_tag(&tag).is_some_and(|packet_type| packet_type.is_named());
+            let mut head = 1;
+            if named {
+                let Some(&name_len) = src.get(5) else {
+                    return Ok(None);
+                };
+                let name_len = (name_len as usize).min(len - 2);
+                if src.len() < 6 + name_len {
+                    return Ok(None);
+                }
+
+                head = 2 + name_len;
+            }
+
+            let header = src.split_to(4 + head).freeze();
+            let name = named.then(|| header.slice(6..));
+
+            self.skipping = len - head;
+            return Ok(Some(Frame::Oversized { tag, name, len }));
+        }
+
+        if src.len() < 4 + len {
+            src.reserve(4 + len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(4);
+        Ok(Some(Frame::Complete(src.split_to(len))))
+    }
+}
+
+impl Default for ViciCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for ViciCodec {
+    type Item = Packet;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> error::Result<Option<Packet>> {
+        match self.decode_frame(src)? {
+            Some(Frame::Complete(bytes)) => Packet::deserialize(bytes.freeze(), false).map(Some),
+            Some(Frame::Oversized { len, .. }) => Err(Error::data(ErrorCode::FrameTooLarge(len, self.max_frame_size))),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder<Packet> for ViciCodec {
+    type Error = Error;
+
+    fn encode(&mut self, packet: Packet, dst: &mut BytesMut) -> error::Result<()> {
+        self.encode(&packet, dst)
+    }
+}
+
+impl Encoder<&Packet> for ViciCodec {
+    type Error = Error;
+
+    fn encode(&mut self, packet: &Packet, dst: &mut BytesMut) -> error::Result<()> {
+        packet.encode(dst)
+    }
+}
+
+/// Decodes frames for the listener, which parses the packets by itself so that a malformed or oversized packet does not end the stream.
+pub(crate) struct FrameCodec(pub ViciCodec);
+
+impl Decoder for FrameCodec {
+    type Item = Frame;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Frame>> {
+        self.0.decode_frame(src)
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+
+use super::{packet::Packet, packet_type::PacketType};
+use crate::error::{self, Error, ErrorCode};
+
+/// When and in which order the listener has received an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Receipt {
+    pub sequence: u64,
+    pub received: SystemTime,
+}
+
+/// A message of an event along with how it has been received, which is yielded by [`Client::subscribe_events`].
+///
+/// [`Client::subscribe_events`]: crate::Client::subscribe_events
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event<U> {
+    name: Bytes,
+    sequence: u64,
+    received: SystemTime,
+    size: usize,
+    message: U,
+}
+
+impl<U> Event<U> {
+    /// Returns the name of the event.
+    pub fn name(&self) -> &str {
+        std::str::from_utf8(&self.name).expect("names are decoded as UTF-8 by the listener")
+    }
+
+    /// Returns the sequence number of the event, which is given by the client in the order the events are received.
+    ///
+    /// Every event received on the connection is counted from zero, whether or not it is delivered to this subscriber, so the numbers of the events
+    /// of a single subscription are increasing but not necessarily consecutive. The count starts over on every new connection.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns the time at which the event has been read from the connection, before it is buffered for the subscriber.
+    pub fn received(&self) -> SystemTime {
+        self.received
+    }
+
+    /// Returns the size in bytes of the encoded payload of the event.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the message of the event.
+    pub fn message(&self) -> &U {
+        &self.message
+    }
+
+    /// Consumes the event and returns its message.
+    pub fn into_message(self) -> U {
+        self.message
+    }
+}
+
+impl<U> Event<U>
+where
+    U: DeserializeOwned,
+{
+    /// Deserializes the message of an event received by the listener.
+    pub(crate) fn from_packet(packet: Packet) -> error::Result<Self> {
+        let name = match packet.packet_type() {
+            PacketType::Event(name) => name.clone(),
+            packet_type => return Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string()))),
+        };
+        let receipt = packet.receipt().expect("events are stamped by the listener");
+        let size = packet.payload().len();
+
+        Ok(Self {
+            name,
+            sequence: receipt.sequence,
+            received: receipt.received,
+            size,
+            message: packet.into_message()?,
+        })
+    }
+}
@@ -1,52 +1,97 @@
 use std::{
     collections::{HashMap, VecDeque},
     io,
+    time::SystemTime,
 };
 
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
 use tokio::{
-    io::{AsyncRead, AsyncWrite},
-    select,
-    sync::mpsc::{Receiver, UnboundedReceiver, UnboundedSender},
+    io::{self as tokio_io, AsyncRead, AsyncWrite, ReadHalf, WriteHalf},
+    pin, select,
+    sync::{
+        mpsc::{Receiver, UnboundedReceiver, UnboundedSender},
+        watch,
+    },
     task,
 };
+use tokio_util::codec::FramedRead;
 
 use crate::error::{self, Error, ErrorCode};
 
-use super::{packet::Packet, packet_type::PacketType, Handler};
+use super::{
+    buffer::SendError,
+    codec::{Frame, FrameCodec, ViciCodec},
+    event::Receipt,
+    packet::Packet,
+    packet_type::PacketType,
+    session::Session,
+    ClientOptions, Handler,
+};
 
 type CommandReceiver = Receiver<(Packet, Handler)>;
 type EventReceiver = Receiver<(Packet, String, Registration, Handler)>;
 type ErrorHandlerReceiver = UnboundedReceiver<UnboundedSender<Error>>;
 
 pub(crate) enum Registration {
+    /// Subscribes the handler to every message of the event.
     Register,
-    Unregister,
+
+    /// Registers the event for a streamed request. The handler only receives messages while its command is in progress.
+    RegisterStream,
+
+    /// Detaches the given subscriber from the event.
+    Unregister(Handler),
+}
+
+/// A handler attached to a registered event.
+struct Subscriber {
+    handler: Handler,
+    streamed: bool,
 }
 
 pub(crate) struct Listener<S> {
-    session: S,
-    command_queue: VecDeque<Handler>,
-    event_queue: VecDeque<(String, Registration, Handler)>,
-    event_subscriptions: HashMap<String, Handler>,
+    reader: Option<ReadHalf<S>>,
+    session: Session<WriteHalf<S>>,
+    command_queue: VecDeque<(Packet, Handler)>,
+    active_command: Option<Handler>,
+    event_queue: VecDeque<(String, Vec<(Registration, Handler)>)>,
+    event_subscriptions: HashMap<Bytes, Vec<Subscriber>>,
+    event_sequence: u64,
     error_handler: Option<UnboundedSender<Error>>,
+    options: ClientOptions,
 }
 
 impl<S> Listener<S>
 where
     S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
 {
-    pub fn new(session: S) -> Self {
+    pub fn new(session: S, options: ClientOptions) -> Self {
+        let (reader, session) = tokio_io::split(session);
         Self {
-            session,
+            reader: Some(reader),
+            session: Session::new(session),
             command_queue: VecDeque::new(),
+            active_command: None,
             event_queue: VecDeque::new(),
             event_subscriptions: HashMap::new(),
+            event_sequence: 0,
             error_handler: None,
+            options,
         }
     }
 
-    pub fn start(mut self, mut commands: CommandReceiver, mut events: EventReceiver, mut error_handler: ErrorHandlerReceiver) -> task::JoinHandle<()> {
+    pub fn start(
+        mut self,
+        mut commands: CommandReceiver,
+        mut events: EventReceiver,
+        mut error_handler: ErrorHandlerReceiver,
+        connected: watch::Sender<bool>,
+    ) -> task::JoinHandle<()> {
         tokio::spawn(async move {
+            let incoming = Self::incoming(self.reader.take().unwrap(), self.options.max_frame_size);
+            pin!(incoming);
+
             loop {
                 let result = select! {
                     Some((packet, handler)) = commands.recv() => {
@@ -59,124 +104,334 @@ where
                         self.error_handler = handler;
                         continue;
                     },
-                    res = Packet::receive(&mut self.session) => {
-                        self.on_response(res).await
+                    res = incoming.next() => match res {
+                        Some(res) => self.on_response(res).await,
+                        None => break,
                     },
                 };
 
-                if let (Some(error_handler), Err(e)) = (&self.error_handler, result) {
-                    if error_handler.send(e).is_err() {
-                        self.error_handler = None;
-                    }
+                self.report(result);
+
+                // A failure to write ends the connection just like EOF.
+                if self.session.is_broken() {
+                    break;
                 }
             }
+
+            connected.send_replace(false);
+
+            commands.close();
+            while let Ok((_, handler)) = commands.try_recv() {
+                Self::disconnect(handler);
+            }
+
+            events.close();
+            while let Ok((_, _, _, handler)) = events.try_recv() {
+                Self::disconnect(handler);
+            }
+
+            self.shutdown();
         })
     }
 
+    /// Reads packets from the session until it reaches EOF. A packet being read is kept inside the stream while the listener is busy with other
+    /// requests, so that no bytes are lost when the other branches of `select!` win.
+    ///
+    /// Packets exceeding `max_frame_size` are skipped, while any IO errors end the stream after being yielded.
+    fn incoming(reader: ReadHalf<S>, max_frame_size: usize) -> impl Stream<Item = io::Result<Frame>> {
+        FramedRead::new(reader, FrameCodec(ViciCodec::with_max_frame_size(max_frame_size)))
+    }
+
+    fn report(&mut self, result: error::Result<()>) {
+        if let (Some(error_handler), Err(e)) = (&self.error_handler, result) {
+            if error_handler.send(e).is_err() {
+                self.error_handler = None;
+            }
+        }
+    }
+
+    /// Fails every pending request and subscription after the connection has been closed.
+    fn shutdown(&mut self) {
+        self.report(Err(Error::data(ErrorCode::Disconnected)));
+
+        let handlers = self
+            .active_command
+            .take()
+            .into_iter()
+            .chain(self.command_queue.drain(..).map(|(_, handler)| handler))
+            .chain(self.event_queue.drain(..).flat_map(|(_, waiters)| waiters).map(|(_, handler)| handler))
+            .chain(self.event_subscriptions.drain().flat_map(|(_, subscribers)| subscribers).map(|s| s.handler));
+
+        for handler in handlers {
+            Self::disconnect(handler);
+        }
+    }
+
+    fn disconnect(handler: Handler) {
+        // Deliver the error after the messages the handler has not consumed yet without blocking the listener.
+        let _ = handler.force_send(Err(Error::data(ErrorCode::Disconnected)));
+    }
+
     async fn on_command_request(&mut self, packet: Packet, handler: Handler) -> error::Result<()> {
-        match packet.send(&mut self.session).await {
-            Ok(()) => self.command_queue.push_back(handler),
-            Err(e) => handler
-                .send(Err(e.into()))
-                .await
-                .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileCommandRequest))?,
+        self.command_queue.push_back((packet, handler));
+        self.send_next_command().await
+    }
+
+    /// Sends the next queued command unless another one is still waiting for its response, since the IKE daemon does not support sequence numbers
+    /// that associate a request and response.
+    async fn send_next_command(&mut self) -> error::Result<()> {
+        while self.active_command.is_none() {
+            let Some((packet, handler)) = self.command_queue.pop_front() else {
+                break;
+            };
+
+            // The caller has already given up, e.g. on a timeout.
+            if handler.is_closed() {
+                continue;
+            }
+
+            match self.session.send(&packet).await {
+                Ok(()) => self.active_command = Some(handler),
+                Err(e) => handler
+                    .send(Err(e))
+                    .await
+                    .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileCommandRequest))?,
+            }
         }
 
         Ok(())
     }
 
     async fn on_event_request(&mut self, packet: Packet, event: String, registration: Registration, handler: Handler) -> error::Result<()> {
-        match packet.send(&mut self.session).await {
-            Ok(()) => {},
-            Err(e) => {
-                handler
-                    .send(Err(e.into()))
-                    .await
-                    .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileEventRequest(event)))?;
+        match registration {
+            Registration::Register | Registration::RegisterStream => {
+                // The event is already registered with the daemon; attach the handler to the existing subscription.
+                if let Some(subscribers) = self.event_subscriptions.get_mut(event.as_bytes()) {
+                    Self::confirm(&handler).map_err(|_| Error::data(ErrorCode::HandlerClosedWhileEventRequest(event)))?;
+
+                    let streamed = matches!(registration, Registration::RegisterStream);
+                    subscribers.push(Subscriber { handler, streamed });
+                    return Ok(());
+                }
 
-                return Ok(());
+                // The registration is in progress; the handler will be confirmed along with it.
+                if let Some((_, waiters)) = self
+                    .event_queue
+                    .iter_mut()
+                    .rev()
+                    .find(|(e, waiters)| *e == event && matches!(waiters.first(), Some((Registration::Register | Registration::RegisterStream, _))))
+                {
+                    waiters.push((registration, handler));
+                    return Ok(());
+                }
             },
+            Registration::Unregister(ref subscriber) => {
+                let Some(subscribers) = self
+                    .event_subscriptions
+                    .get_mut(event.as_bytes())
+                    .filter(|subscribers| subscribers.iter().any(|s| s.handler.same_channel(subscriber)))
+                else {
+                    // The subscriber has not been registered, e.g. when the registration has failed. A registration still in progress is reverted
+                    // once it is confirmed, since the subscriber has given up by then.
+                    let _ = Self::confirm(&handler);
+                    return Ok(());
+                };
+
+                subscribers.retain(|s| !s.handler.same_channel(subscriber));
+
+                // Other subscribers remain; keep the event registered with the daemon.
+                if !subscribers.is_empty() {
+                    let _ = Self::confirm(&handler);
+                    return Ok(());
+                }
+
+                self.event_subscriptions.remove(event.as_bytes());
+            },
+        }
+
+        if let Err(e) = self.session.send(&packet).await {
+            return self
+                .fail(&handler, e)
+                .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileEventRequest(event)));
         }
 
-        self.event_queue.push_back((event, registration, handler));
+        self.event_queue.push_back((event, vec![(registration, handler)]));
         Ok(())
     }
 
-    async fn on_response(&mut self, res: io::Result<Packet>) -> error::Result<()> {
-        let packet = res?;
+    /// Confirms an event request without blocking the listener. The caller may have stopped waiting for the confirmation, e.g. when a streamed request
+    /// has been dropped.
+    fn confirm(handler: &Handler) -> Result<(), SendError> {
+        handler.force_send(Ok(Packet::new(PacketType::EventConfirm, Bytes::new())))
+    }
+
+    /// Fails an event request with `error` without blocking the listener. The error is reported instead if nobody waits for the result any longer.
+    fn fail(&mut self, handler: &Handler, error: Error) -> Result<(), SendError> {
+        if handler.is_closed() {
+            self.report(Err(error));
+            return Ok(());
+        }
+
+        handler.force_send(Err(error))
+    }
+
+    /// Fails whoever waits for the skipped packet with the error: the command if it is a response, or the subscribers if it is a message of an event.
+    async fn on_oversized(&mut self, tag: u8, name: Option<Bytes>, len: usize) -> error::Result<()> {
+        let error = || Error::data(ErrorCode::FrameTooLarge(len, self.options.max_frame_size));
+
+        match (PacketType::from_tag(&tag), name) {
+            (Some(packet_type @ PacketType::CmdResponse), _) => match self.active_command.take() {
+                Some(handler) => {
+                    let result = handler
+                        .send(Err(error()))
+                        .await
+                        .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileStreaming(packet_type.to_string())));
+
+                    self.send_next_command().await?;
+                    result
+                },
+                None => Err(error()),
+            },
+            (Some(PacketType::Event(_)), Some(name)) => {
+                let recipients = self.recipients(&name);
+                if recipients.is_empty() {
+                    return Err(error());
+                }
+
+                let mut delivered = false;
+                for subscriber in recipients {
+                    delivered |= subscriber.handler.send(Err(error())).await.is_ok();
+                }
+
+                if !delivered {
+                    return Err(Error::data(ErrorCode::HandlerClosedWhileStreaming(PacketType::Event(name).to_string())));
+                }
+
+                Ok(())
+            },
+            _ => Err(error()),
+        }
+    }
+
+    /// Returns the subscribers that receive the messages of the event. Streamed requests only receive the messages issued while their command is in
+    /// progress.
+    fn recipients(&self, event: &[u8]) -> Vec<&Subscriber> {
+        let Some(subscribers) = self.event_subscriptions.get(event) else {
+            return Vec::new();
+        };
+
+        subscribers
+            .iter()
+            .filter(|s| !s.streamed || self.active_command.as_ref().is_some_and(|active| active.same_channel(&s.handler)))
+            .collect()
+    }
+
+    async fn on_response(&mut self, res: io::Result<Frame>) -> error::Result<()> {
+        // Errors while decoding a packet only affect the packet itself.
+        let mut packet = match res? {
+            Frame::Complete(bytes) => Packet::deserialize(bytes.freeze(), self.options.strict)?,
+            Frame::Oversized { tag, name, len } => return self.on_oversized(tag, name, len).await,
+        };
+
+        // Every event is numbered in the order it arrives, whether or not anyone has subscribed to it.
+        if let PacketType::Event(_) = packet.packet_type() {
+            packet.set_receipt(Receipt {
+                sequence: self.event_sequence,
+                received: SystemTime::now(),
+            });
+            self.event_sequence += 1;
+        }
         match packet.packet_type() {
-            packet_type @ PacketType::CmdResponse => match self.command_queue.pop_front() {
+            PacketType::CmdResponse | PacketType::CmdUnknown if self.active_command.as_ref().is_some_and(|handler| handler.is_closed()) => {
+                // The caller has already given up, e.g. on a timeout; discard the late response.
+                self.active_command = None;
+                self.send_next_command().await?;
+            },
+            packet_type @ PacketType::CmdResponse => match self.active_command.take() {
                 Some(handler) => {
-                    handler
+                    let packet_type = packet_type.to_string();
+                    let result = handler
                         .send(Ok(packet))
                         .await
-                        .map_err(|e| Error::data(ErrorCode::HandlerClosedWhileStreaming(e.0.unwrap().packet_type().to_string())))?;
+                        .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileStreaming(packet_type)));
+
+                    self.send_next_command().await?;
+                    result?;
                 },
                 None => {
                     return Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())));
                 },
             },
-            packet_type @ PacketType::CmdUnknown => match self.command_queue.pop_front() {
+            packet_type @ PacketType::CmdUnknown => match self.active_command.take() {
                 Some(handler) => {
-                    handler
+                    let result = handler
                         .send(Err(Error::data(ErrorCode::UnknownCmd)))
                         .await
-                        .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileStreaming(packet_type.to_string())))?;
+                        .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileStreaming(packet_type.to_string())));
+
+                    self.send_next_command().await?;
+                    result?;
                 },
                 None => {
                     return Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())));
                 },
             },
             packet_type @ PacketType::EventConfirm => match self.event_queue.pop_front() {
-                Some((event, Registration::Register, handler)) => {
-                    handler
-                        .send(Ok(packet))
-                        .await
-                        .map_err(|e| Error::data(ErrorCode::HandlerClosedWhileStreaming(e.0.unwrap().packet_type().to_string())))?;
+                Some((event, waiters)) => {
+                    let registered = matches!(waiters.first(), Some((Registration::Register | Registration::RegisterStream, _)));
+                    let mut subscribers = Vec::with_capacity(waiters.len());
+                    for (registration, handler) in waiters {
+                        // Subscribers that have given up are left out.
+                        let delivered = Self::confirm(&handler).is_ok();
+                        match registration {
+                            Registration::Register if delivered => subscribers.push(Subscriber { handler, streamed: false }),
+                            Registration::RegisterStream if delivered => subscribers.push(Subscriber { handler, streamed: true }),
+                            Registration::Register | Registration::RegisterStream | Registration::Unregister(_) => {},
+                        }
+                    }
 
-                    self.event_subscriptions.insert(event, handler);
-                },
-                Some((event, Registration::Unregister, handler)) => {
-                    self.event_subscriptions.remove(&event);
+                    if !registered {
+                        return Ok(());
+                    }
 
-                    handler
-                        .send(Ok(packet))
-                        .await
-                        .map_err(|e| Error::data(ErrorCode::HandlerClosedWhileStreaming(e.0.unwrap().packet_type().to_string())))?;
+                    // Every subscriber has already given up, e.g. on a timeout; revert the registration.
+                    if subscribers.is_empty() {
+                        let packet = Packet::new(PacketType::EventUnregister(event.clone().into()), Bytes::new());
+                        self.session.send(&packet).await?;
+                        self.event_queue.push_back((event, vec![]));
+                        return Ok(());
+                    }
+
+                    self.event_subscriptions.insert(event.into(), subscribers);
                 },
                 None => {
                     return Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())));
                 },
             },
             packet_type @ PacketType::EventUnknown => match self.event_queue.pop_front() {
-                Some((event, Registration::Register, handler)) => {
-                    handler
-                        .send(Err(Error::data(ErrorCode::UnknownEvent(event))))
-                        .await
-                        .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileStreaming(packet_type.to_string())))?;
-                },
-                Some((event, Registration::Unregister, handler)) => {
-                    self.event_subscriptions.remove(&event);
-
-                    handler
-                        .send(Err(Error::data(ErrorCode::UnknownEvent(event))))
-                        .await
-                        .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileStreaming(packet_type.to_string())))?;
+                Some((event, waiters)) => {
+                    for (_, handler) in waiters {
+                        let error = Error::data(ErrorCode::UnknownEvent(event.clone()));
+                        self.fail(&handler, error)
+                            .map_err(|_| Error::data(ErrorCode::HandlerClosedWhileStreaming(packet_type.to_string())))?;
+                    }
                 },
                 None => {
                     return Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())));
                 },
             },
-            packet_type @ PacketType::Event(name) => match self.event_subscriptions.get(name) {
-                Some(handler) => {
-                    handler
-                        .send(Ok(packet))
-                        .await
-                        .map_err(|e| Error::data(ErrorCode::HandlerClosedWhileStreaming(e.0.unwrap().packet_type().to_string())))?;
-                },
-                None => {
-                    return Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())));
-                },
+            packet_type @ PacketType::Event(name) if self.event_subscriptions.contains_key(name) => {
+                let recipients = self.recipients(name);
+
+                // Every subscriber receives its own copy; closed ones are about to be unregistered.
+                let mut delivered = recipients.is_empty();
+                for subscriber in recipients {
+                    delivered |= subscriber.handler.send(Ok(packet.clone())).await.is_ok();
+                }
+
+                if !delivered {
+                    return Err(Error::data(ErrorCode::HandlerClosedWhileStreaming(packet_type.to_string())));
+                }
             },
             packet_type => {
                 return Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())));
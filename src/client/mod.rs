@@ -1,49 +1,146 @@
-use async_stream::try_stream;
-use futures_util::Stream;
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{future::Future, mem, sync::Arc, time::Duration};
+
+use async_stream::{stream, try_stream};
+use bytes::Bytes;
+use futures_util::{
+    future, pin_mut,
+    stream::{self, StreamExt, TryStreamExt},
+    Stream,
+};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Deserialize, Serialize,
+};
 use tokio::{
     io::{AsyncRead, AsyncWrite},
-    sync::mpsc::{self, Sender, UnboundedSender},
-    task,
+    runtime,
+    sync::{
+        mpsc::{self, error::TrySendError, Sender, UnboundedSender},
+        watch,
+    },
+    task, time,
 };
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
+pub use self::{
+    buffer::Buffer,
+    event::Event,
+    reconnect::{Backoff, ConnectionState, ReconnectingClient},
+};
+
 use self::{
     listener::{Listener, Registration},
     packet::Packet,
     packet_type::PacketType,
 };
-use crate::error::{self, Error, ErrorCode};
+use crate::{
+    command::{Command, StreamCommand},
+    error::{self, Error, ErrorCode},
+};
 
 pub mod tcp;
 
 #[cfg(unix)]
 pub mod unix;
 
+mod buffer;
+mod codec;
+mod event;
 mod listener;
 mod packet;
 mod packet_type;
+pub mod raw;
+mod reconnect;
 mod session;
 
-type Handler = Sender<error::Result<Packet>>;
+type Handler = buffer::Sender;
 type CommandSender = Sender<(Packet, Handler)>;
 type EventSender = Sender<(Packet, String, Registration, Handler)>;
 type ErrorHandlerSender = UnboundedSender<UnboundedSender<Error>>;
 
 #[derive(Deserialize)]
-struct Response {
+pub(crate) struct Response {
     success: Option<bool>,
     errmsg: Option<String>,
 }
 
+impl Response {
+    /// Fails with the error message if the command has not succeeded.
+    pub(crate) fn check(self) -> error::Result<()> {
+        match self.success {
+            Some(false) => Err(Error::data(ErrorCode::CommandFailed(self.errmsg))),
+            Some(true) | None => Ok(()),
+        }
+    }
+}
+
+/// A response of a streamed request call made by [`Client::stream_request_with_response`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamResponse<U, R> {
+    /// A message of the event streamed for the request.
+    Event(U),
+
+    /// The final response to the command, which comes after all the events.
+    Response(R),
+}
+
+/// Settings of the connection to the IKE daemon, which are applied when a client is created.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClientOptions {
+    /// The maximum size in bytes of a packet read from the IKE daemon, which defaults to 1 MiB.
+    ///
+    /// A larger packet is skipped without being buffered and fails with an error categorized as [`Category::Protocol`]. If the packet is a response to a
+    /// request, the request fails with the error. If it is a message of an event, streamed requests fail with the error, while subscriptions yield it
+    /// and go on. Otherwise the error is reported to [`Client::listen_for_errors`].
+    ///
+    /// [`Category::Protocol`]: crate::error::Category::Protocol
+    pub max_frame_size: usize,
+
+    /// Whether to reject packets whose names are not valid UTF-8, which defaults to false. Invalid bytes in the names are replaced with
+    /// `U+FFFD REPLACEMENT CHARACTER` unless this is true.
+    ///
+    /// The rejected packets are reported to [`Client::listen_for_errors`] with an error categorized as [`Category::Protocol`].
+    ///
+    /// [`Category::Protocol`]: crate::error::Category::Protocol
+    pub strict: bool,
+
+    /// Whether subscriptions and streamed requests go on after a message fails to be deserialized, which defaults to false.
+    ///
+    /// Either way the message is yielded as an error categorized as [`Category::Data`], which carries the payload of the message as
+    /// [`Error::payload`]. The stream ends after the error unless this is true.
+    ///
+    /// [`Category::Data`]: crate::error::Category::Data
+    pub tolerate_invalid_messages: bool,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            max_frame_size: 1024 * 1024,
+            strict: false,
+            tolerate_invalid_messages: false,
+        }
+    }
+}
+
 /// A structure to interact with the IKE daemon using the VICI protocol.
+///
+/// The client can be cloned cheaply to be shared among tasks. All the clones use the same connection, and the connection is closed when the last clone
+/// is dropped.
+#[derive(Clone)]
 pub struct Client {
     commands: CommandSender,
     events: EventSender,
     error_handler: ErrorHandlerSender,
-    listener: task::JoinHandle<()>,
+    timeout: Option<Duration>,
+    tolerate_invalid_messages: bool,
+    connected: watch::Receiver<bool>,
+    _listener: Arc<ListenerGuard>,
 }
 
+/// Aborts the listener when the last clone of the client is dropped.
+struct ListenerGuard(task::JoinHandle<()>);
+
 impl Client {
     /// Creates an rsvici client from a stream.
     ///
@@ -55,24 +152,91 @@ impl Client {
     /// [`rsvici::tcp::connect`]: tcp::connect
     #[cfg_attr(unix, doc = "[`rsvici::unix::connect`]: unix::connect")]
     pub fn new<S>(session: S) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        Self::with_options(session, ClientOptions::default())
+    }
+
+    /// Creates an rsvici client from a stream with the given `options`.
+    ///
+    /// See [`Client::new`] for details.
+    pub fn with_options<S>(session: S, options: ClientOptions) -> Self
     where
         S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
     {
         let (commands_tx, commands_rx) = mpsc::channel(8);
         let (events_tx, events_rx) = mpsc::channel(8);
         let (error_handler_tx, error_handler_rx) = mpsc::unbounded_channel();
+        let (connected_tx, connected_rx) = watch::channel(true);
+        let listener = Listener::new(session, options).start(commands_rx, events_rx, error_handler_rx, connected_tx);
 
         Self {
             commands: commands_tx,
             events: events_tx,
             error_handler: error_handler_tx,
-            listener: Listener::new(session).start(commands_rx, events_rx, error_handler_rx),
+            timeout: None,
+            tolerate_invalid_messages: options.tolerate_invalid_messages,
+            connected: connected_rx,
+            _listener: Arc::new(ListenerGuard(listener)),
         }
     }
 
+    /// Returns true if the connection to the IKE daemon is still open.
+    pub fn is_connected(&self) -> bool {
+        is_connected(&self.connected)
+    }
+
+    /// Waits until the connection to the IKE daemon is closed, e.g. when the daemon has been restarted.
+    ///
+    /// Once the connection is closed, the pending calls fail with an error categorized as [`Category::Disconnected`], and the client can no longer be
+    /// used.
+    ///
+    /// # Example
+    #[cfg_attr(unix, doc = "```no_run")]
+    #[cfg_attr(not(unix), doc = "```ignore")]
+    /// use std::error::Error;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn Error>> {
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///
+    ///     client.closed().await;
+    ///     println!("Disconnected from the daemon");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`Category::Disconnected`]: crate::error::Category::Disconnected
+    pub async fn closed(&self) {
+        let mut connected = self.connected.clone();
+        let _ = connected.wait_for(|connected| !connected).await;
+    }
+
+    /// Returns the default timeout for the calls made by this client.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Sets the default timeout for the calls made by this client. `None` disables the timeout, which is the default.
+    ///
+    /// The timeout limits how long a request call waits for its response, how long a streamed request call waits for each of its messages, and how long
+    /// a subscription waits for the event to be registered and unregistered. It applies to this client and the clones made from it afterwards.
+    ///
+    /// The time spent waiting for the connection to take the call, e.g. while it is held back by a subscriber whose buffer is full, counts towards the
+    /// timeout. Calls that run out of time fail with an error categorized as [`Category::Timeout`]. Responses that arrive late are discarded so that the
+    /// following calls still receive their own responses.
+    ///
+    /// [`Category::Timeout`]: crate::error::Category::Timeout
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
     /// Makes a request call and receives a response.
     ///
-    /// Since the IKE daemon does not support sequence numbers that associate a request and response, do not make more than one request call at a time.
+    /// Since the IKE daemon does not support sequence numbers that associate a request and response, concurrent request calls are sent to the daemon one
+    /// at a time in the order they are made.
     ///
     /// For the list of available commands, see [Client-initiated commands][].
     ///
@@ -94,7 +258,7 @@ impl Client {
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn Error>> {
-    ///     let mut client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
     ///
     ///     let version: Version = client.request("version", ()).await?;
     ///     println!("Version: {:#?}", version);
@@ -104,26 +268,56 @@ impl Client {
     /// ```
     ///
     /// [Client-initiated commands]: https://github.com/strongswan/strongswan/blob/5.9.5/src/libcharon/plugins/vici/README.md#client-initiated-commands
-    pub async fn request<T, U>(&mut self, cmd: &str, message: T) -> error::Result<U>
+    pub async fn request<T, U>(&self, cmd: &str, message: T) -> error::Result<U>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+    {
+        self.make_request(cmd, message, self.timeout, false).await
+    }
+
+    /// Makes a request call and receives a response within `timeout`, overriding the default timeout of the client.
+    ///
+    /// See [`Client::request`] for details.
+    pub async fn request_with_timeout<T, U>(&self, cmd: &str, message: T, timeout: Duration) -> error::Result<U>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+    {
+        self.make_request(cmd, message, Some(timeout), false).await
+    }
+
+    /// Makes a request call. The response fails the call if it reports a failure when `check_response` is true, so that `U` only describes the
+    /// success.
+    pub(crate) async fn make_request<T, U>(&self, cmd: &str, message: T, timeout: Option<Duration>, check_response: bool) -> error::Result<U>
     where
         T: Serialize,
         U: DeserializeOwned,
     {
-        let (tx, mut rx) = mpsc::channel(1);
+        let (tx, mut rx) = buffer::channel(Buffer::default());
 
-        let req = Packet::from(PacketType::CmdRequest(cmd.to_string()), message)?;
-        self.commands.send((req, tx)).await.map_err(|_| Error::data(ErrorCode::ListenerClosed))?;
+        let req = Packet::from(PacketType::CmdRequest(cmd.to_string().into()), message)?;
+        let packet = within(timeout, async {
+            self.commands.send((req, tx)).await.map_err(|_| not_taken(&self.connected))?;
+            next(&mut rx).await
+        })
+        .await?;
 
-        match rx.recv().await {
-            Some(Ok(packet)) => packet.message().map_err(Into::into),
-            Some(Err(e)) => Err(e),
-            None => Err(Error::data(ErrorCode::ListenerClosed)),
+        if check_response {
+            packet.message::<Response>()?.check()?;
         }
+
+        packet.into_message()
     }
 
     /// Makes a streamed request call and iterates through its responses.
     ///
-    /// Since the IKE daemon does not support sequence numbers that associate a request and response, do not make more than one request call at a time.
+    /// Since the IKE daemon does not support sequence numbers that associate a request and response, concurrent request calls are sent to the daemon one
+    /// at a time in the order they are made. Other commands are held back until the response to the streamed request arrives, so that only the events
+    /// issued for this request are iterated.
+    ///
+    /// The event is unregistered once the response arrives, and the stream fails if the daemon does not confirm it. If the stream fails or is dropped
+    /// before the response arrives, the event is unregistered in the background, and a failure to do so is reported to [`Client::listen_for_errors`].
     ///
     /// For the list of available commands, see [Client-initiated commands][] and [Server-issued events][].
     ///
@@ -156,7 +350,7 @@ impl Client {
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn Error>> {
-    ///     let mut client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
     ///
     ///     let conns = client.stream_request::<(), Conns>("list-conns", "list-conn", ());
     ///     pin_mut!(conns);
@@ -173,98 +367,241 @@ impl Client {
     /// [futures-util]:              https://docs.rs/futures-util
     /// [Client-initiated commands]: https://github.com/strongswan/strongswan/blob/5.9.5/src/libcharon/plugins/vici/README.md#client-initiated-commands
     /// [Server-issued events]:      https://github.com/strongswan/strongswan/blob/5.9.5/src/libcharon/plugins/vici/README.md#server-issued-events
-    pub fn stream_request<T, U>(&mut self, cmd: &str, event: &str, message: T) -> impl Stream<Item = error::Result<U>>
+    pub fn stream_request<T, U>(&self, cmd: &str, event: &str, message: T) -> impl Stream<Item = error::Result<U>>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+    {
+        self.make_stream_request_events(cmd, event, message, self.timeout)
+    }
+
+    /// Makes a streamed request call and iterates through its responses, overriding the default timeout of the client. The stream fails when no message
+    /// arrives within `timeout`.
+    ///
+    /// See [`Client::stream_request`] for details.
+    pub fn stream_request_with_timeout<T, U>(&self, cmd: &str, event: &str, message: T, timeout: Duration) -> impl Stream<Item = error::Result<U>>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+    {
+        self.make_stream_request_events(cmd, event, message, Some(timeout))
+    }
+
+    /// Makes a streamed request call and iterates through its responses, followed by the final response to the command.
+    ///
+    /// Some commands reply with their own data in the final response as well as the streamed events, such as the number of IKE_SAs that have been
+    /// terminated. The stream yields [`StreamResponse::Event`] for each event, and then ends with [`StreamResponse::Response`] once the event has
+    /// been unregistered. If the command fails, the stream fails instead of yielding the final response.
+    ///
+    /// See [`Client::stream_request`] for details.
+    ///
+    /// # Example
+    #[cfg_attr(unix, doc = "```no_run")]
+    #[cfg_attr(not(unix), doc = "```ignore")]
+    /// use std::error::Error;
+    ///
+    /// use futures_util::{
+    ///     stream::TryStreamExt,
+    ///     pin_mut,
+    /// };
+    /// use rsvici::StreamResponse;
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Debug, Serialize)]
+    /// struct Terminate {
+    ///     ike: String,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct ControlLog {
+    ///     group: String,
+    ///     level: u32,
+    ///     msg: String,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Terminated {
+    ///     matches: u32,
+    ///     terminated: u32,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn Error>> {
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///
+    ///     let terminate = Terminate { ike: "gw-gw".to_string() };
+    ///     let stream = client.stream_request_with_response::<Terminate, ControlLog, Terminated>("terminate", "control-log", terminate);
+    ///     pin_mut!(stream);
+    ///
+    ///     while let Some(response) = stream.try_next().await? {
+    ///         match response {
+    ///             StreamResponse::Event(log) => println!("Log: {:#?}", log),
+    ///             StreamResponse::Response(terminated) => println!("Terminated: {:#?}", terminated),
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn stream_request_with_response<T, U, R>(&self, cmd: &str, event: &str, message: T) -> impl Stream<Item = error::Result<StreamResponse<U, R>>>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+        R: DeserializeOwned,
+    {
+        self.make_stream_request(cmd, event, message, self.timeout)
+    }
+
+    /// Makes a streamed request call and iterates through its responses, followed by the final response to the command, overriding the default timeout
+    /// of the client. The stream fails when no message arrives within `timeout`.
+    ///
+    /// See [`Client::stream_request_with_response`] for details.
+    pub fn stream_request_with_response_and_timeout<T, U, R>(
+        &self,
+        cmd: &str,
+        event: &str,
+        message: T,
+        timeout: Duration,
+    ) -> impl Stream<Item = error::Result<StreamResponse<U, R>>>
     where
         T: Serialize,
         U: DeserializeOwned,
+        R: DeserializeOwned,
+    {
+        self.make_stream_request(cmd, event, message, Some(timeout))
+    }
+
+    fn make_stream_request<T, U, R>(
+        &self,
+        cmd: &str,
+        event: &str,
+        message: T,
+        timeout: Option<Duration>,
+    ) -> impl Stream<Item = error::Result<StreamResponse<U, R>>>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+        R: DeserializeOwned,
     {
         let events = self.events.clone();
         let commands = self.commands.clone();
+        let connected = self.connected.clone();
 
         let cmd = cmd.to_string();
         let event = event.to_string();
+        let tolerate_invalid_messages = self.tolerate_invalid_messages;
 
-        try_stream! {
-            let (tx, mut rx) = mpsc::channel(1);
+        // Invalid messages are yielded as items so that the stream can go on after them.
+        let stream = try_stream! {
+            let (tx, mut rx) = buffer::channel(Buffer::default());
             let cmd_response: Response;
+            let reply: R;
 
-            let req = Packet::from(PacketType::EventRegister(event.clone()), ())?;
-            events
-                .send((req, event.clone(), Registration::Register, tx.clone()))
-                .await
-                .map_err(|_| Error::data(ErrorCode::ListenerClosed))?;
+            // The guard is in place before the registration is requested, so that the event is unregistered even if the stream is dropped before the
+            // confirmation is received.
+            let registration = StreamRegistration {
+                events: events.clone(),
+                connected: connected.clone(),
+                event: event.clone(),
+                handler: Some(tx.clone()),
+            };
 
-            match rx.recv().await {
-                Some(Ok(_)) => {},
-                Some(Err(e)) => Err(e)?,
-                None => Err(Error::data(ErrorCode::ListenerClosed))?,
-            }
+            let req = Packet::from(PacketType::EventRegister(event.clone().into()), ())?;
+            within(timeout, async {
+                events
+                    .send((req, event, Registration::RegisterStream, tx.clone()))
+                    .await
+                    .map_err(|_| not_taken(&connected))?;
 
-            let req = Packet::from(PacketType::CmdRequest(cmd), message)?;
-            commands.send((req, tx)).await.map_err(|_| Error::data(ErrorCode::ListenerClosed))?;
+                next(&mut rx).await
+            })
+            .await?;
+
+            let req = Packet::from(PacketType::CmdRequest(cmd.into()), message)?;
+            within(timeout, async { commands.send((req, tx)).await.map_err(|_| not_taken(&connected)) }).await?;
 
             loop {
-                match rx.recv().await {
-                    Some(Ok(packet)) => match packet.packet_type() {
-                        PacketType::CmdResponse => {
-                            match packet.message() {
-                                Ok(resp) => {
-                                    cmd_response = resp;
-                                    break;
-                                },
-                                Err(e) => {
-                                    Err(Error::from(e))?;
-                                },
-                            }
-                        },
-                        PacketType::Event(_) => {
-                            match packet.message() {
-                                Ok(item) => {
-                                    yield item;
-                                },
-                                Err(e) => {
-                                    Err(Error::from(e))?;
-                                },
-                            }
-                        },
-                        packet_type => {
-                            Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())))?;
-                        },
+                let packet = receive(&mut rx, timeout).await?;
+                match packet.packet_type() {
+                    PacketType::CmdResponse => {
+                        cmd_response = packet.message()?;
+                        reply = packet.into_message()?;
+                        break;
                     },
-                    Some(Err(e)) => {
-                        Err(e)?;
+                    PacketType::Event(_) => {
+                        match packet.into_message() {
+                            Ok(item) => {
+                                yield Ok(StreamResponse::Event(item));
+                            },
+                            Err(e) if tolerate_invalid_messages => {
+                                yield Err(e);
+                            },
+                            Err(e) => {
+                                Err(e)?;
+                            },
+                        }
                     },
-                    None => {
-                        Err(Error::data(ErrorCode::ListenerClosed))?;
+                    packet_type => {
+                        Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())))?;
                     },
                 }
             }
 
-            let (unregister_tx, mut unregister_rx) = mpsc::channel(1);
+            registration.unregister(timeout).await?;
 
-            let req = Packet::from(PacketType::EventUnregister(event.clone()), ())?;
-            events
-                .send((req, event, Registration::Unregister, unregister_tx))
-                .await
-                .map_err(|_| Error::data(ErrorCode::ListenerClosed))?;
+            cmd_response.check()?;
 
-            match unregister_rx.recv().await {
-                Some(Ok(_)) => {},
-                Some(Err(e)) => Err(e)?,
-                None => Err(Error::data(ErrorCode::ListenerClosed))?,
-            }
+            yield Ok(StreamResponse::Response(reply));
+        };
 
-            match cmd_response.success {
-                Some(true) => {},
-                Some(false) => Err(Error::data(ErrorCode::CommandFailed(cmd_response.errmsg)))?,
-                None => {},
-            }
-        }
+        stream.map(|item: error::Result<error::Result<StreamResponse<U, R>>>| item?)
+    }
+
+    fn make_stream_request_events<T, U>(&self, cmd: &str, event: &str, message: T, timeout: Option<Duration>) -> impl Stream<Item = error::Result<U>>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+    {
+        stream_events(self.make_stream_request::<T, U, IgnoredAny>(cmd, event, message, timeout))
+    }
+
+    /// Makes a request call of the command and receives its response, whose type is determined by the command. See [`rsvici::command`] for how to
+    /// define commands, and [`Client::request`] for the other details.
+    ///
+    /// [`rsvici::command`]: crate::command
+    pub async fn call<C>(&self, command: C) -> error::Result<C::Response>
+    where
+        C: Command,
+    {
+        self.request(C::NAME, command).await
+    }
+
+    /// Makes a streamed request call of the command and iterates through the messages of its event, whose types are determined by the command. See
+    /// [`rsvici::command`] for how to define commands, and [`Client::stream_request`] for the other details.
+    ///
+    /// [`rsvici::command`]: crate::command
+    pub fn call_stream<C>(&self, command: C) -> impl Stream<Item = error::Result<C::Item>>
+    where
+        C: StreamCommand,
+    {
+        self.stream_request(C::NAME, C::EVENT, command)
+    }
+
+    /// Makes a streamed request call of the command and iterates through the messages of its event, followed by its response. See
+    /// [`Client::call_stream`] and [`Client::stream_request_with_response`] for details.
+    pub fn call_stream_with_response<C>(&self, command: C) -> impl Stream<Item = error::Result<StreamResponse<C::Item, C::Response>>>
+    where
+        C: StreamCommand,
+    {
+        self.stream_request_with_response(C::NAME, C::EVENT, command)
     }
 
     /// Subscribes to an event and iterates through its messages. It is safe to subscribe to events while making other requests at a time. The rsvici will
     /// automatically unsubscribe from the event when the returned stream is dropped.
     ///
+    /// The same event can be subscribed to more than once. Only the first subscription registers the event with the IKE daemon, every subscriber receives
+    /// all of its messages, and the event is unregistered when the last subscriber is dropped.
+    ///
     /// For the list of available events, see [Server-issued events][].
     ///
     /// You may also want to have a look at the documentation for [async-stream][] and [futures-util][].
@@ -290,7 +627,7 @@ impl Client {
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn Error>> {
-    ///     let mut client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
     ///
     ///     let logs = client.subscribe::<Log>("log");
     ///     tokio::spawn(async move {
@@ -312,70 +649,348 @@ impl Client {
     /// [async-stream]:         https://docs.rs/async-stream
     /// [futures-util]:         https://docs.rs/futures-util
     /// [Server-issued events]: https://github.com/strongswan/strongswan/blob/5.9.5/src/libcharon/plugins/vici/README.md#server-issued-events
-    pub fn subscribe<U>(&mut self, event: &str) -> impl Stream<Item = error::Result<U>>
+    pub fn subscribe<U>(&self, event: &str) -> impl Stream<Item = error::Result<U>>
     where
         U: DeserializeOwned,
     {
-        let events = self.events.clone();
-        let event = event.to_string();
+        self.make_subscription(event, self.timeout, Buffer::default(), Packet::into_message)
+    }
+
+    /// Subscribes to an event and iterates through its messages, overriding the default timeout of the client. The timeout only applies to registering
+    /// and unregistering the event; the stream waits for its messages indefinitely.
+    ///
+    /// See [`Client::subscribe`] for details.
+    pub fn subscribe_with_timeout<U>(&self, event: &str, timeout: Duration) -> impl Stream<Item = error::Result<U>>
+    where
+        U: DeserializeOwned,
+    {
+        self.make_subscription(event, Some(timeout), Buffer::default(), Packet::into_message)
+    }
+
+    /// Subscribes to an event and iterates through its messages, buffering them according to `buffer` until they are consumed. [`Client::subscribe`]
+    /// uses [`Buffer::Block`] with the size of one, which holds back the connection while the stream is not consumed.
+    ///
+    /// If the buffer drops messages, the stream yields an error categorized as [`Category::Lagged`] with the number of messages lost, and then continues
+    /// with the next message.
+    ///
+    /// See [`Client::subscribe`] for details.
+    ///
+    /// # Example
+    #[cfg_attr(unix, doc = "```no_run")]
+    #[cfg_attr(not(unix), doc = "```ignore")]
+    /// use std::error::Error;
+    ///
+    /// use futures_util::{
+    ///     stream::StreamExt,
+    ///     pin_mut,
+    /// };
+    /// use rsvici::Buffer;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Log {
+    ///     group: String,
+    ///     level: u32,
+    ///     thread: u32,
+    ///     msg: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn Error>> {
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///
+    ///     let logs = client.subscribe_with_buffer::<Log>("log", Buffer::DropOldest(1024));
+    ///     pin_mut!(logs);
+    ///
+    ///     while let Some(log) = logs.next().await {
+    ///         match log {
+    ///             Ok(log) => println!("Log: {:#?}", log),
+    ///             Err(e) if e.is_lagged() => println!("Lost {} logs", e.lost_messages().unwrap()),
+    ///             Err(e) => return Err(e.into()),
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`Category::Lagged`]: crate::error::Category::Lagged
+    pub fn subscribe_with_buffer<U>(&self, event: &str, buffer: Buffer) -> impl Stream<Item = error::Result<U>>
+    where
+        U: DeserializeOwned,
+    {
+        self.make_subscription(event, self.timeout, buffer, Packet::into_message)
+    }
 
-        try_stream! {
-            let (tx, mut rx) = mpsc::channel(1);
+    /// Subscribes to an event and iterates through its messages along with their names, sequence numbers, times of receipt, and sizes, which help to
+    /// correlate events with each other and to measure how long they have been buffered.
+    ///
+    /// See [`Client::subscribe`] for details.
+    ///
+    /// # Example
+    #[cfg_attr(unix, doc = "```no_run")]
+    #[cfg_attr(not(unix), doc = "```ignore")]
+    /// use std::error::Error;
+    ///
+    /// use futures_util::{
+    ///     stream::TryStreamExt,
+    ///     pin_mut,
+    /// };
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Log {
+    ///     group: String,
+    ///     level: u32,
+    ///     thread: u32,
+    ///     msg: String,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn Error>> {
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///
+    ///     let logs = client.subscribe_events::<Log>("log");
+    ///     pin_mut!(logs);
+    ///
+    ///     while let Some(log) = logs.try_next().await? {
+    ///         let lag = log.received().elapsed()?;
+    ///         println!("#{} ({} bytes, {:?} ago): {:#?}", log.sequence(), log.size(), lag, log.message());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn subscribe_events<U>(&self, event: &str) -> impl Stream<Item = error::Result<Event<U>>>
+    where
+        U: DeserializeOwned,
+    {
+        self.make_subscription(event, self.timeout, Buffer::default(), Event::from_packet)
+    }
+
+    /// Subscribes to an event and iterates through its messages along with how they have been received, overriding the default timeout of the client.
+    /// The timeout only applies to registering and unregistering the event; the stream waits for its messages indefinitely.
+    ///
+    /// See [`Client::subscribe_events`] for details.
+    pub fn subscribe_events_with_timeout<U>(&self, event: &str, timeout: Duration) -> impl Stream<Item = error::Result<Event<U>>>
+    where
+        U: DeserializeOwned,
+    {
+        self.make_subscription(event, Some(timeout), Buffer::default(), Event::from_packet)
+    }
+
+    /// Subscribes to an event and iterates through its messages along with how they have been received, buffering them according to `buffer` until they
+    /// are consumed.
+    ///
+    /// See [`Client::subscribe_events`] and [`Client::subscribe_with_buffer`] for details.
+    pub fn subscribe_events_with_buffer<U>(&self, event: &str, buffer: Buffer) -> impl Stream<Item = error::Result<Event<U>>>
+    where
+        U: DeserializeOwned,
+    {
+        self.make_subscription(event, self.timeout, buffer, Event::from_packet)
+    }
+
+    fn make_subscription<U>(
+        &self,
+        event: &str,
+        timeout: Option<Duration>,
+        buffer: Buffer,
+        decode: fn(Packet) -> error::Result<U>,
+    ) -> impl Stream<Item = error::Result<U>> {
+        let tolerate_invalid_messages = self.tolerate_invalid_messages;
+        stream::once(self.register(event, timeout, buffer))
+            .map_ok(move |rx| events(rx, decode, tolerate_invalid_messages))
+            .try_flatten()
+    }
+
+    /// Subscribes to several events at once and iterates through their messages in the order they arrive, each of which is tagged with the name of its
+    /// event. The events are unregistered when the returned stream is dropped.
+    ///
+    /// The stream fails if any of the events cannot be registered, and ends right away if no events are given. See [`Client::subscribe`] for the other
+    /// details.
+    ///
+    /// # Example
+    #[cfg_attr(unix, doc = "```no_run")]
+    #[cfg_attr(not(unix), doc = "```ignore")]
+    /// use std::error::Error;
+    ///
+    /// use futures_util::{
+    ///     stream::TryStreamExt,
+    ///     pin_mut,
+    /// };
+    /// use rsvici::Value;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn Error>> {
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///
+    ///     let updates = client.subscribe_many::<Value>(&["ike-updown", "ike-rekey", "child-updown", "child-rekey"]);
+    ///     pin_mut!(updates);
+    ///
+    ///     while let Some((event, update)) = updates.try_next().await? {
+    ///         println!("{}: {:#?}", event, update);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn subscribe_many<U>(&self, names: &[&str]) -> impl Stream<Item = error::Result<(String, U)>>
+    where
+        U: DeserializeOwned,
+    {
+        let requests = self.events.clone();
+        let connected = self.connected.clone();
+        let timeout = self.timeout;
+        let tolerate_invalid_messages = self.tolerate_invalid_messages;
 
-            let req = Packet::from(PacketType::EventRegister(event.clone()), ())?;
-            events
-                .send((req, event.clone(), Registration::Register, tx.clone()))
-                .await
-                .map_err(|_| Error::data(ErrorCode::ListenerClosed))?;
+        // Each event is registered once even if it is listed more than once.
+        let names = names.iter().fold(Vec::<String>::with_capacity(names.len()), |mut unique, name| {
+            if !unique.iter().any(|n| n == name) {
+                unique.push(name.to_string());
+            }
+            unique
+        });
 
-            match rx.recv().await {
-                Some(Ok(_)) => {},
-                Some(Err(e)) => Err(e)?,
-                None => Err(Error::data(ErrorCode::ListenerClosed))?,
+        stream! {
+            if names.is_empty() {
+                return;
             }
 
+            // Every event delivers its messages to the same channel so that they are iterated in the order they arrive.
+            let (tx, mut rx) = buffer::channel(Buffer::default());
+
+            // Every event is recorded before it is registered, so that dropping the stream unregisters it however far its registration
+            // has got. The listener merely confirms unregistering an event that has not been registered, and reverts one that is confirmed
+            // after the stream has been dropped.
+            let (registered_tx, mut registered_rx) = mpsc::unbounded_channel();
+            let cleanup = (requests.clone(), connected.clone(), tx.clone());
             tokio::spawn(async move {
+                let (requests, connected, tx) = cleanup;
                 tx.closed().await;
 
-                let (unregister_tx, mut unregister_rx) = mpsc::channel(1);
-
-                let req = Packet::from(PacketType::EventUnregister(event.clone()), ())?;
-                events
-                    .send((req, event, Registration::Unregister, unregister_tx))
-                    .await
-                    .map_err(|_| Error::data(ErrorCode::ListenerClosed))?;
-
-                match unregister_rx.recv().await {
-                    Some(Ok(_)) => Ok(()),
-                    Some(Err(e)) => Err(e),
-                    None => Err(Error::data(ErrorCode::ListenerClosed)),
+                while let Ok(name) = registered_rx.try_recv() {
+                    let _ = unregister(&requests, &connected, name, tx.clone(), timeout).await;
                 }
             });
 
-            loop {
-                match rx.recv().await {
-                    Some(Ok(packet)) => match (packet.packet_type(), packet.message()) {
-                        (PacketType::Event(_), Ok(item)) => {
-                            yield item;
+            // The events are registered one by one, since the confirmations do not tell which event they are for.
+            for name in names {
+                let req = match Packet::from(PacketType::EventRegister(name.clone().into()), ()) {
+                    Ok(req) => req,
+                    Err(e) => {
+                        yield Err(e);
+                        return;
+                    },
+                };
+                let _ = registered_tx.send(name.clone());
+                let request = (req, name, Registration::Register, tx.clone());
+                if let Err(e) = within(timeout, async { requests.send(request).await.map_err(|_| not_taken(&connected)) }).await {
+                    yield Err(e);
+                    return;
+                }
+
+                // The events registered first may deliver their messages before the others are confirmed.
+                loop {
+                    let packet = match receive(&mut rx, timeout).await {
+                        Ok(packet) => packet,
+                        Err(e) if e.is_lagged() || e.is_protocol() => {
+                            yield Err(e);
+                            continue;
                         },
-                        (PacketType::Event(_), Err(e)) => {
-                            Err(Error::from(e))?;
+                        Err(e) => {
+                            yield Err(e);
+                            return;
                         },
-                        (packet_type, _) => {
-                            Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())))?;
+                    };
+
+                    match packet.packet_type() {
+                        PacketType::EventConfirm => break,
+                        PacketType::Event(_) => match tagged_message(packet) {
+                            Ok(item) => yield Ok(item),
+                            Err(e) if tolerate_invalid_messages && e.payload().is_some() => yield Err(e),
+                            Err(e) => {
+                                yield Err(e);
+                                return;
+                            },
                         },
-                    },
-                    Some(Err(e)) => {
-                        Err(e)?;
-                    },
-                    None => {
-                        Err(Error::data(ErrorCode::ListenerClosed))?;
-                    },
+                        packet_type => {
+                            yield Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())));
+                            return;
+                        },
+                    }
                 }
             }
+            drop(registered_tx);
+
+            let stream = events(rx, tagged_message, tolerate_invalid_messages);
+            pin_mut!(stream);
+
+            while let Some(item) = stream.next().await {
+                yield item;
+            }
         }
     }
 
+    /// Registers the event and returns the receiver of its messages. The event is unregistered when the receiver is dropped.
+    pub(crate) fn register(&self, event: &str, timeout: Option<Duration>, buffer: Buffer) -> impl Future<Output = error::Result<buffer::Receiver>> {
+        let events = self.events.clone();
+        let connected = self.connected.clone();
+        let event = event.to_string();
+
+        async move {
+            let (tx, mut rx) = buffer::channel(buffer);
+
+            // The cleanup is in place before the registration is requested, so that the event is unregistered even if the receiver is dropped
+            // before the confirmation is received.
+            let cleanup = (events.clone(), connected.clone(), event.clone(), tx.clone());
+            tokio::spawn(async move {
+                let (events, connected, event, tx) = cleanup;
+                tx.closed().await;
+                unregister(&events, &connected, event, tx, timeout).await
+            });
+
+            let req = Packet::from(PacketType::EventRegister(event.clone().into()), ())?;
+            within(timeout, async {
+                events.send((req, event, Registration::Register, tx)).await.map_err(|_| not_taken(&connected))?;
+
+                next(&mut rx).await
+            })
+            .await?;
+
+            Ok(rx)
+        }
+    }
+
+    /// Makes a request call with an encoded payload and receives the encoded payload of the response, without serializing or deserializing them.
+    ///
+    /// Both payloads are in the format of the VICI protocol; see [`rsvici::raw`] for details. The response shares the buffer it has been read into
+    /// and can be deserialized into a message that borrows from it with [`serde_vici::from_slice`]. See [`Client::request`] for the other details.
+    ///
+    /// [`rsvici::raw`]: crate::raw
+    pub async fn raw_request(&self, cmd: &str, payload: impl Into<Bytes>) -> error::Result<Bytes> {
+        let (tx, mut rx) = buffer::channel(Buffer::default());
+
+        let req = Packet::new(PacketType::CmdRequest(cmd.to_string().into()), payload);
+        let packet = within(self.timeout, async {
+            self.commands.send((req, tx)).await.map_err(|_| not_taken(&self.connected))?;
+            next(&mut rx).await
+        })
+        .await?;
+
+        Ok(packet.into_payload())
+    }
+
+    /// Subscribes to an event and iterates through the encoded payloads of its messages, without deserializing them.
+    ///
+    /// The payloads are in the format of the VICI protocol; see [`rsvici::raw`] for details. See [`Client::subscribe`] for the other details.
+    ///
+    /// [`rsvici::raw`]: crate::raw
+    pub fn raw_subscribe(&self, event: &str) -> impl Stream<Item = error::Result<Bytes>> {
+        stream::once(self.register(event, self.timeout, Buffer::default()))
+            .map_ok(|rx| events(rx, |packet| Ok(packet.into_payload()), false))
+            .try_flatten()
+    }
+
     /// Listens for background errors, such as unexpected messages or unhandled packets, and iterates them.
     ///
     /// There can be only one error handler. If you call this method a second (or more) time, the registered listener will be dropped.
@@ -402,7 +1017,7 @@ impl Client {
     ///
     /// #[tokio::main]
     /// async fn main() -> Result<(), Box<dyn Error>> {
-    ///     let mut client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
     ///
     ///     let mut errors = client.listen_for_errors();
     ///     tokio::spawn(async move {
@@ -416,7 +1031,7 @@ impl Client {
     ///     Ok(())
     /// }
     /// ```
-    pub fn listen_for_errors(&mut self) -> impl Stream<Item = Error> {
+    pub fn listen_for_errors(&self) -> impl Stream<Item = Error> {
         let (tx, rx) = mpsc::unbounded_channel();
         let _ = self.error_handler.send(tx);
 
@@ -424,8 +1039,166 @@ impl Client {
     }
 }
 
-impl Drop for Client {
+/// Iterates through the events of a streamed request call, leaving out its final response.
+pub(crate) fn stream_events<U, R>(stream: impl Stream<Item = error::Result<StreamResponse<U, R>>>) -> impl Stream<Item = error::Result<U>> {
+    stream.try_filter_map(|response| match response {
+        StreamResponse::Event(item) => future::ready(Ok(Some(item))),
+        StreamResponse::Response(_) => future::ready(Ok(None)),
+    })
+}
+
+/// Deserializes the message of an event along with the name of the event.
+fn tagged_message<U>(packet: Packet) -> error::Result<(String, U)>
+where
+    U: DeserializeOwned,
+{
+    let name = match packet.packet_type() {
+        PacketType::Event(name) => String::from_utf8_lossy(name).into_owned(),
+        packet_type => return Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string()))),
+    };
+
+    Ok((name, packet.into_message()?))
+}
+
+/// Iterates through the messages of a registered event, decoding each of them with `decode`. The stream continues after reporting the messages dropped
+/// by the buffer or skipped for their size, and after reporting the messages that fail to be decoded if `tolerate_invalid_messages` is true.
+pub(crate) fn events<U>(
+    mut rx: buffer::Receiver,
+    decode: fn(Packet) -> error::Result<U>,
+    tolerate_invalid_messages: bool,
+) -> impl Stream<Item = error::Result<U>> {
+    stream! {
+        loop {
+            let packet = match receive(&mut rx, None).await {
+                Ok(packet) => packet,
+                Err(e) if e.is_lagged() || e.is_protocol() => {
+                    yield Err(e);
+                    continue;
+                },
+                Err(e) => {
+                    yield Err(e);
+                    break;
+                },
+            };
+
+            match packet.packet_type() {
+                PacketType::Event(_) => match decode(packet) {
+                    Ok(item) => {
+                        yield Ok(item);
+                    },
+                    Err(e) if tolerate_invalid_messages => {
+                        yield Err(e);
+                    },
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    },
+                },
+                packet_type => {
+                    yield Err(Error::data(ErrorCode::UnexpectedPacket(packet_type.to_string())));
+                    break;
+                },
+            }
+        }
+    }
+}
+
+/// Keeps the event of a streamed request registered until the request completes. If the stream fails or is dropped before that, the event is
+/// unregistered in the background, and a failure to unregister it is reported to [`Client::listen_for_errors`].
+struct StreamRegistration {
+    events: EventSender,
+    connected: watch::Receiver<bool>,
+    event: String,
+    handler: Option<Handler>,
+}
+
+impl StreamRegistration {
+    /// Unregisters the event and waits for the daemon to confirm it.
+    async fn unregister(mut self, timeout: Option<Duration>) -> error::Result<()> {
+        match self.handler.take() {
+            Some(handler) => unregister(&self.events, &self.connected, mem::take(&mut self.event), handler, timeout).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for StreamRegistration {
+    fn drop(&mut self) {
+        let Some(handler) = self.handler.take() else {
+            return;
+        };
+        let Ok(req) = Packet::from(PacketType::EventUnregister(self.event.clone().into()), ()) else {
+            return;
+        };
+
+        // Nobody waits for the confirmation; the listener reports the failure instead.
+        let (unregister_tx, _) = buffer::channel(Buffer::default());
+        let request = (req, mem::take(&mut self.event), Registration::Unregister(handler), unregister_tx);
+
+        if let Err(TrySendError::Full(request)) = self.events.try_send(request) {
+            let events = self.events.clone();
+            if let Ok(runtime) = runtime::Handle::try_current() {
+                runtime.spawn(async move { events.send(request).await });
+            }
+        }
+    }
+}
+
+/// Unregisters the event from the subscriber `handler` and waits for the daemon to confirm it.
+async fn unregister(events: &EventSender, connected: &watch::Receiver<bool>, event: String, handler: Handler, timeout: Option<Duration>) -> error::Result<()> {
+    let (unregister_tx, mut unregister_rx) = buffer::channel(Buffer::default());
+
+    let req = Packet::from(PacketType::EventUnregister(event.clone().into()), ())?;
+    within(timeout, async {
+        events
+            .send((req, event, Registration::Unregister(handler), unregister_tx))
+            .await
+            .map_err(|_| not_taken(connected))?;
+
+        next(&mut unregister_rx).await
+    })
+    .await
+    .map(|_| ())
+}
+
+/// Returns true if the listener is still running and the connection to the IKE daemon is still open.
+fn is_connected(connected: &watch::Receiver<bool>) -> bool {
+    connected.has_changed().is_ok() && *connected.borrow()
+}
+
+/// The error for a call the listener no longer takes. Once the connection has been closed, such a call fails like the calls that were in progress.
+fn not_taken(connected: &watch::Receiver<bool>) -> Error {
+    if is_connected(connected) {
+        Error::data(ErrorCode::ListenerClosed)
+    } else {
+        Error::data(ErrorCode::Disconnected)
+    }
+}
+
+/// Waits for the next packet from the listener, failing once `timeout` elapses.
+async fn receive(rx: &mut buffer::Receiver, timeout: Option<Duration>) -> error::Result<Packet> {
+    within(timeout, next(rx)).await
+}
+
+/// Waits for the next packet from the listener.
+async fn next(rx: &mut buffer::Receiver) -> error::Result<Packet> {
+    match rx.recv().await {
+        Some(res) => res,
+        None => Err(Error::data(ErrorCode::ListenerClosed)),
+    }
+}
+
+/// Runs `future` to completion, failing once `timeout` elapses. Handing a request over to the listener counts towards the timeout, since the
+/// listener may be held back, e.g. by a subscriber whose buffer is full.
+async fn within<T>(timeout: Option<Duration>, future: impl Future<Output = error::Result<T>>) -> error::Result<T> {
+    match timeout {
+        Some(timeout) => time::timeout(timeout, future).await.map_err(|_| Error::data(ErrorCode::Timeout))?,
+        None => future.await,
+    }
+}
+
+impl Drop for ListenerGuard {
     fn drop(&mut self) {
-        self.listener.abort();
+        self.0.abort();
     }
 }
@@ -1,53 +1,136 @@
-use std::io;
+use bytes::{BufMut, Bytes, BytesMut};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use serde::{de::DeserializeOwned, Serialize};
+use super::{event::Receipt, PacketType};
+use crate::error::{self, Error, ErrorCode};
 
-use super::PacketType;
-
-pub(crate) struct Packet {
+/// A message of the VICI protocol, consisting of its type and an encoded payload.
+///
+/// The payload of a received packet shares the buffer it has been read into, so that cloning the packet or its payload does not copy the bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Packet {
     packet_type: PacketType,
-    payload: Vec<u8>,
+    payload: Bytes,
+    receipt: Option<Receipt>,
 }
 
 impl Packet {
-    pub fn new(packet_type: PacketType, payload: Vec<u8>) -> Self {
-        Self { packet_type, payload }
+    /// Creates a packet from its type and encoded payload.
+    pub fn new(packet_type: PacketType, payload: impl Into<Bytes>) -> Self {
+        Self {
+            packet_type,
+            payload: payload.into(),
+            receipt: None,
+        }
     }
 
-    pub fn from<T>(packet_type: PacketType, message: T) -> io::Result<Self>
+    pub(crate) fn from<T>(packet_type: PacketType, message: T) -> error::Result<Self>
     where
         T: Serialize,
     {
         let payload = serde_vici::to_vec(&message)?;
 
-        Ok(Self { packet_type, payload })
+        Ok(Self::new(packet_type, payload))
     }
 
-    pub fn message<T>(&self) -> io::Result<T>
+    /// Deserializes the payload of the packet. The message may borrow its strings and bytes from the payload instead of copying them.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::error::Error;
+    ///
+    /// use futures_util::stream::TryStreamExt;
+    /// use rsvici::raw::ViciCodec;
+    /// use serde::Deserialize;
+    /// use tokio::net::TcpListener;
+    /// use tokio_util::codec::FramedRead;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Log<'a> {
+    ///     group: &'a str,
+    ///     level: u32,
+    ///     msg: &'a str,
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn Error>> {
+    ///     let listener = TcpListener::bind("127.0.0.1:4502").await?;
+    ///     let (stream, _) = listener.accept().await?;
+    ///
+    ///     let mut packets = FramedRead::new(stream, ViciCodec::new());
+    ///     while let Some(packet) = packets.try_next().await? {
+    ///         let log: Log = packet.message()?;
+    ///         println!("[{}] {}", log.group, log.msg);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn message<'de, T>(&'de self) -> error::Result<T>
     where
-        T: DeserializeOwned,
+        T: Deserialize<'de>,
     {
         let message = serde_vici::from_slice(&self.payload)?;
 
         Ok(message)
     }
 
+    /// Deserializes the payload of a received message, keeping the payload in the error if it fails.
+    pub(crate) fn into_message<T>(self) -> error::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        match serde_vici::from_slice(&self.payload) {
+            Ok(message) => Ok(message),
+            Err(e) => Err(Error::data(ErrorCode::InvalidMessage(e, self.payload))),
+        }
+    }
+
+    /// Returns the type of the packet.
     pub fn packet_type(&self) -> &PacketType {
         &self.packet_type
     }
 
-    pub fn serialize(&self) -> io::Result<Vec<u8>> {
-        let mut buf = vec![];
+    /// Returns the encoded payload of the packet.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Consumes the packet and returns its encoded payload.
+    pub fn into_payload(self) -> Bytes {
+        self.payload
+    }
+
+    /// Returns when and in which order the listener has received the packet if it is an event.
+    pub(crate) fn receipt(&self) -> Option<Receipt> {
+        self.receipt
+    }
+
+    /// Records when and in which order the listener has received the packet.
+    pub(crate) fn set_receipt(&mut self, receipt: Receipt) {
+        self.receipt = Some(receipt);
+    }
+
+    /// Encodes the packet with its length prefix into `dst`, failing without writing anything if it does not fit in the prefix.
+    pub(crate) fn encode(&self, dst: &mut BytesMut) -> error::Result<()> {
+        let mut header = Vec::with_capacity(2 + u8::MAX as usize);
+        self.packet_type.marshal(&mut header)?;
+
+        let len = header.len() + self.payload.len();
+        let len = u32::try_from(len).map_err(|_| Error::data(ErrorCode::PacketTooLarge(len)))?;
 
-        self.packet_type.marshal(&mut buf)?;
-        buf.extend_from_slice(&self.payload);
+        dst.reserve(4 + len as usize);
+        dst.put_u32(len);
+        dst.put_slice(&header);
+        dst.put_slice(&self.payload);
 
-        Ok(buf)
+        Ok(())
     }
 
-    pub fn deserialize(slice: &[u8]) -> io::Result<Packet> {
-        let (buf, packet_type) = PacketType::unmarshal(slice)?;
+    /// Decodes a packet without its length prefix. The payload is sliced from `bytes` without copying.
+    pub(crate) fn deserialize(bytes: Bytes, strict: bool) -> error::Result<Packet> {
+        let (payload, packet_type) = PacketType::unmarshal(&bytes, strict)?;
 
-        Ok(Packet::new(packet_type, buf.to_vec()))
+        Ok(Packet::new(packet_type, payload))
     }
 }
@@ -1,8 +1,16 @@
 use std::{fmt::Display, io};
 
-pub(crate) enum PacketType {
+use bytes::Bytes;
+
+use crate::error::{self, Error, ErrorCode};
+
+/// The type of a packet, which is named for requests, event registrations, and event messages.
+///
+/// The name of a received packet is sliced from the buffer it has been read into without copying, and is valid UTF-8.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PacketType {
     /// A named request message.
-    CmdRequest(String),
+    CmdRequest(Bytes),
 
     /// An unnamed response message for a request.
     CmdResponse,
@@ -11,10 +19,10 @@ pub(crate) enum PacketType {
     CmdUnknown,
 
     /// A named event registration request.
-    EventRegister(String),
+    EventRegister(Bytes),
 
     /// A named event deregistration request.
-    EventUnregister(String),
+    EventUnregister(Bytes),
 
     /// An unnamed response for successful event (de-)registration.
     EventConfirm,
@@ -23,10 +31,11 @@ pub(crate) enum PacketType {
     EventUnknown,
 
     /// A named event message.
-    Event(String),
+    Event(Bytes),
 }
 
 impl PacketType {
+    /// Returns the numeric value of the type on the wire.
     pub fn tag(&self) -> u8 {
         match self {
             Self::CmdRequest(_) => 0,
@@ -40,7 +49,12 @@ impl PacketType {
         }
     }
 
-    pub fn from_tag(tag: &u8) -> Option<Self> {
+    /// Returns true if the type is followed by a name on the wire.
+    pub(crate) fn is_named(&self) -> bool {
+        matches!(self, Self::CmdRequest(_) | Self::EventRegister(_) | Self::EventUnregister(_) | Self::Event(_))
+    }
+
+    pub(crate) fn from_tag(tag: &u8) -> Option<Self> {
         match tag {
             0 => Some(Self::CmdRequest(Default::default())),
             1 => Some(Self::CmdResponse),
@@ -54,45 +68,56 @@ impl PacketType {
         }
     }
 
-    pub fn marshal<W>(&self, writer: &mut W) -> io::Result<()>
+    /// Writes the type and name of a packet, failing without writing anything if the name does not fit in its length prefix.
+    pub(crate) fn marshal<W>(&self, writer: &mut W) -> error::Result<()>
     where
         W: io::Write,
     {
-        writer.write_all(&[self.tag()])?;
-
         match &self {
             PacketType::CmdRequest(name) | PacketType::EventRegister(name) | PacketType::EventUnregister(name) | PacketType::Event(name) => {
-                writer.write_all(&[name.len() as u8])?;
-                writer.write_all(name.as_bytes())?;
+                let name_len = u8::try_from(name.len()).map_err(|_| Error::data(ErrorCode::NameTooLong(String::from_utf8_lossy(name).into_owned())))?;
+
+                writer.write_all(&[self.tag(), name_len])?;
+                writer.write_all(name)?;
+            },
+            _ => {
+                writer.write_all(&[self.tag()])?;
             },
-            _ => {},
         }
 
         Ok(())
     }
 
-    pub fn unmarshal(input: &[u8]) -> io::Result<(&[u8], Self)> {
-        let (packet_type, input) = input.split_first().ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "EOF"))?;
+    /// Reads the type and name of a packet, and returns them along with the rest of `input`. Names that are not valid UTF-8 are rejected if `strict`
+    /// is true, or have their invalid bytes replaced otherwise.
+    pub(crate) fn unmarshal(input: &Bytes, strict: bool) -> error::Result<(Bytes, Self)> {
+        let truncated = || Error::data(ErrorCode::TruncatedPacket(input.to_vec()));
+
+        let (tag, rest) = input.split_first().ok_or_else(truncated)?;
 
-        let mut packet_type = PacketType::from_tag(packet_type).ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "unknown type"))?;
+        let mut packet_type = PacketType::from_tag(tag).ok_or_else(|| Error::data(ErrorCode::UnknownPacketType(*tag, input.to_vec())))?;
         match packet_type {
             PacketType::CmdRequest(ref mut name)
             | PacketType::EventRegister(ref mut name)
             | PacketType::EventUnregister(ref mut name)
             | PacketType::Event(ref mut name) => {
-                let (name_len, input) = input.split_first().ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "EOF"))?;
+                let (name_len, rest) = rest.split_first().ok_or_else(truncated)?;
 
                 let name_len = *name_len as usize;
-                if input.len() < name_len {
-                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "EOF"));
+                if rest.len() < name_len {
+                    return Err(truncated());
                 }
 
-                let (v, input) = input.split_at(name_len);
-                *name = String::from_utf8_lossy(v).into();
+                let (v, rest) = rest.split_at(name_len);
+                *name = match std::str::from_utf8(v) {
+                    Ok(_) => input.slice_ref(v),
+                    Err(_) if strict => return Err(Error::data(ErrorCode::InvalidName(*tag, input.to_vec()))),
+                    Err(_) => String::from_utf8_lossy(v).into_owned().into(),
+                };
 
-                Ok((input, packet_type))
+                Ok((input.slice_ref(rest), packet_type))
             },
-            _ => Ok((input, packet_type)),
+            _ => Ok((input.slice_ref(rest), packet_type)),
         }
     }
 }
@@ -100,14 +125,14 @@ impl PacketType {
 impl Display for PacketType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
-            PacketType::CmdRequest(name) => f.write_fmt(format_args!("CMD_REQUEST({name})")),
+            PacketType::CmdRequest(name) => f.write_fmt(format_args!("CMD_REQUEST({})", String::from_utf8_lossy(name))),
             PacketType::CmdResponse => f.write_str("CMD_RESPONSE"),
             PacketType::CmdUnknown => f.write_str("CMD_UNKNOWN"),
-            PacketType::EventRegister(name) => f.write_fmt(format_args!("EVENT_REGISTER({name})")),
-            PacketType::EventUnregister(name) => f.write_fmt(format_args!("EVENT_UNREGISTER({name})")),
+            PacketType::EventRegister(name) => f.write_fmt(format_args!("EVENT_REGISTER({})", String::from_utf8_lossy(name))),
+            PacketType::EventUnregister(name) => f.write_fmt(format_args!("EVENT_UNREGISTER({})", String::from_utf8_lossy(name))),
             PacketType::EventConfirm => f.write_str("EVENT_CONFIRM"),
             PacketType::EventUnknown => f.write_str("EVENT_UNKNOWN"),
-            PacketType::Event(name) => f.write_fmt(format_args!("EVENT({name})")),
+            PacketType::Event(name) => f.write_fmt(format_args!("EVENT({})", String::from_utf8_lossy(name))),
         }
     }
 }
@@ -0,0 +1,46 @@
+//! Packets of the VICI protocol without serialization, for proxies, recorders, and debuggers.
+//!
+//! A packet on the wire is prefixed with its length as a 32-bit big-endian integer, followed by its type, its name if the type is named, and the
+//! payload. The payload is left encoded; use [`Packet::message`] or [`serde_vici`][] to decode it, either of which can borrow strings and bytes from
+//! the payload.
+//!
+//! [`serde_vici`]: https://docs.rs/serde_vici
+
+use bytes::{Bytes, BytesMut};
+
+pub use super::{codec::ViciCodec, packet::Packet, packet_type::PacketType};
+use crate::error;
+
+/// Encodes a packet with its length prefix.
+///
+/// Fails with an error categorized as [`Category::Protocol`] if the name of the packet is longer than 255 bytes or the packet is larger than the
+/// length prefix can represent.
+///
+/// [`Category::Protocol`]: crate::error::Category::Protocol
+pub fn encode(packet: &Packet) -> error::Result<Vec<u8>> {
+    let mut frame = BytesMut::new();
+    packet.encode(&mut frame)?;
+
+    Ok(frame.into())
+}
+
+/// Decodes a packet with its length prefix from the beginning of `buf`, and returns it along with the number of bytes it occupies. Returns `None` if
+/// `buf` does not contain the whole packet yet.
+///
+/// Invalid bytes in the name of the packet are replaced with `U+FFFD REPLACEMENT CHARACTER`. Fails with an error categorized as
+/// [`Category::Protocol`] if the type of the packet is unknown or the packet ends before its name.
+///
+/// [`Category::Protocol`]: crate::error::Category::Protocol
+pub fn decode(buf: &[u8]) -> error::Result<Option<(Packet, usize)>> {
+    let Some((len, rest)) = buf.split_first_chunk::<4>() else {
+        return Ok(None);
+    };
+
+    let len = u32::from_be_bytes(*len) as usize;
+    if rest.len() < len {
+        return Ok(None);
+    }
+
+    let packet = Packet::deserialize(Bytes::copy_from_slice(&rest[..len]), false)?;
+    Ok(Some((packet, 4 + len)))
+}
@@ -0,0 +1,416 @@
+use std::{future::Future, io, sync::Arc, time::Duration};
+
+use async_stream::stream;
+use futures_util::{pin_mut, Stream, StreamExt};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Serialize,
+};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        watch,
+    },
+    task, time,
+};
+
+use super::{events, packet::Packet, stream_events, Buffer, Client, Event, StreamResponse};
+use crate::{
+    command::{Command, StreamCommand},
+    error::{self, Error, ErrorCode},
+};
+
+/// A change in the connection of a [`ReconnectingClient`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The connection to the IKE daemon has been established. Events issued while disconnected have been missed if it is a reconnection.
+    Connected,
+
+    /// The connection to the IKE daemon has been closed. A new connection is being established.
+    Disconnected,
+
+    /// The named event has been registered again after a reconnection.
+    Resubscribed(String),
+
+    /// An attempt to connect to the IKE daemon has failed. Another attempt is made after the delay given by the [`Backoff`].
+    ConnectFailed {
+        /// The kind of the I/O error.
+        kind: io::ErrorKind,
+
+        /// The description of the I/O error.
+        message: String,
+    },
+}
+
+/// Delays between attempts to connect to the IKE daemon. The delay starts from `initial` and doubles after every failed attempt up to `max`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    /// The delay after the first failed attempt.
+    pub initial: Duration,
+
+    /// The upper limit of the delay.
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A client that reconnects to the IKE daemon whenever the connection is closed, e.g. when `charon` is restarted.
+///
+/// The subscriptions made by [`ReconnectingClient::subscribe`] are registered again on every new connection, while request calls that are in progress
+/// when the connection is closed fail with an error categorized as [`Category::Disconnected`] and are not retried. Use
+/// [`ReconnectingClient::connection_states`] to know when events may have been missed.
+///
+/// The client can be cloned cheaply to be shared among tasks. The reconnection stops when the last clone is dropped.
+///
+/// # Example
+#[cfg_attr(unix, doc = "```no_run")]
+#[cfg_attr(not(unix), doc = "```ignore")]
+/// use std::error::Error;
+///
+/// use futures_util::{
+///     stream::{StreamExt, TryStreamExt},
+///     pin_mut,
+/// };
+/// use rsvici::ReconnectingClient;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Log {
+///     group: String,
+///     level: u32,
+///     thread: u32,
+///     msg: String,
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn Error>> {
+///     let client = ReconnectingClient::new(|| rsvici::unix::connect("/run/charon.vici"));
+///
+///     let states = client.connection_states();
+///     tokio::spawn(async move {
+///         pin_mut!(states);
+///
+///         while let Some(state) = states.next().await {
+///             println!("State: {:?}", state);
+///         }
+///     });
+///
+///     let logs = client.subscribe::<Log>("log");
+///     pin_mut!(logs);
+///
+///     while let Some(log) = logs.try_next().await? {
+///         println!("Log: {:#?}", log);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`Category::Disconnected`]: crate::error::Category::Disconnected
+#[derive(Clone)]
+pub struct ReconnectingClient {
+    clients: watch::Receiver<Option<Client>>,
+    states: broadcast::Sender<ConnectionState>,
+    backoff: Backoff,
+    _supervisor: Arc<SupervisorGuard>,
+}
+
+/// Stops reconnecting and closes the connection when the last clone of the client is dropped.
+struct SupervisorGuard {
+    supervisor: task::JoinHandle<()>,
+    clients: Arc<watch::Sender<Option<Client>>>,
+}
+
+impl ReconnectingClient {
+    /// Creates a reconnecting client from a function that connects to the IKE daemon, such as [`rsvici::tcp::connect`] or
+    #[cfg_attr(unix, doc = "[`rsvici::unix::connect`],")]
+    #[cfg_attr(not(unix), doc = "`rsvici::unix::connect`,")]
+    /// using the default [`Backoff`].
+    ///
+    /// The connection is established in the background.
+    ///
+    /// [`rsvici::tcp::connect`]: crate::tcp::connect
+    #[cfg_attr(unix, doc = "[`rsvici::unix::connect`]: crate::unix::connect")]
+    pub fn new<F, Fut>(connect: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = io::Result<Client>> + Send + 'static,
+    {
+        Self::with_backoff(connect, Backoff::default())
+    }
+
+    /// Creates a reconnecting client from a function that connects to the IKE daemon, using the given `backoff` between failed attempts.
+    pub fn with_backoff<F, Fut>(connect: F, backoff: Backoff) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = io::Result<Client>> + Send + 'static,
+    {
+        let (clients_tx, clients_rx) = watch::channel(None);
+        let (states_tx, _) = broadcast::channel(16);
+
+        let clients_tx = Arc::new(clients_tx);
+        let supervisor = tokio::spawn(supervise(connect, backoff, clients_tx.clone(), states_tx.clone()));
+
+        Self {
+            clients: clients_rx,
+            states: states_tx,
+            backoff,
+            _supervisor: Arc::new(SupervisorGuard {
+                supervisor,
+                clients: clients_tx,
+            }),
+        }
+    }
+
+    /// Returns the client for the current connection, waiting until the connection is established.
+    pub async fn client(&self) -> error::Result<Client> {
+        current(&mut self.clients.clone()).await
+    }
+
+    /// Iterates through the changes in the connection from now on.
+    pub fn connection_states(&self) -> impl Stream<Item = ConnectionState> {
+        let mut states = self.states.subscribe();
+
+        stream! {
+            loop {
+                match states.recv().await {
+                    Ok(state) => yield state,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+
+    /// Makes a request call on the current connection and receives a response. See [`Client::request`] for details.
+    pub async fn request<T, U>(&self, cmd: &str, message: T) -> error::Result<U>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+    {
+        self.client().await?.request(cmd, message).await
+    }
+
+    /// Makes a streamed request call on the current connection and iterates through its responses. See [`Client::stream_request`] for details.
+    pub fn stream_request<T, U>(&self, cmd: &str, event: &str, message: T) -> impl Stream<Item = error::Result<U>>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+    {
+        stream_events(self.stream_request_with_response::<T, U, IgnoredAny>(cmd, event, message))
+    }
+
+    /// Makes a streamed request call on the current connection and iterates through its responses, followed by the final response to the command. See
+    /// [`Client::stream_request_with_response`] for details.
+    pub fn stream_request_with_response<T, U, R>(&self, cmd: &str, event: &str, message: T) -> impl Stream<Item = error::Result<StreamResponse<U, R>>>
+    where
+        T: Serialize,
+        U: DeserializeOwned,
+        R: DeserializeOwned,
+    {
+        let mut clients = self.clients.clone();
+
+        let cmd = cmd.to_string();
+        let event = event.to_string();
+
+        stream! {
+            let client = match current(&mut clients).await {
+                Ok(client) => client,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                },
+            };
+
+            // The stream ends by itself after an error unless the error only affects the message.
+            let stream = client.stream_request_with_response(&cmd, &event, message);
+            pin_mut!(stream);
+
+            while let Some(item) = stream.next().await {
+                yield item;
+            }
+        }
+    }
+
+    /// Makes a request call of the command on the current connection and receives its response. See [`Client::call`] for details.
+    pub async fn call<C>(&self, command: C) -> error::Result<C::Response>
+    where
+        C: Command,
+    {
+        self.request(C::NAME, command).await
+    }
+
+    /// Makes a streamed request call of the command on the current connection and iterates through the messages of its event. See
+    /// [`Client::call_stream`] for details.
+    pub fn call_stream<C>(&self, command: C) -> impl Stream<Item = error::Result<C::Item>>
+    where
+        C: StreamCommand,
+    {
+        self.stream_request(C::NAME, C::EVENT, command)
+    }
+
+    /// Makes a streamed request call of the command on the current connection and iterates through the messages of its event, followed by its
+    /// response. See [`Client::call_stream_with_response`] for details.
+    pub fn call_stream_with_response<C>(&self, command: C) -> impl Stream<Item = error::Result<StreamResponse<C::Item, C::Response>>>
+    where
+        C: StreamCommand,
+    {
+        self.stream_request_with_response(C::NAME, C::EVENT, command)
+    }
+
+    /// Subscribes to an event and iterates through its messages across reconnections. See [`Client::subscribe`] for details.
+    ///
+    /// The event is registered again on every new connection, which is published as [`ConnectionState::Resubscribed`]. A registration that times out
+    /// is retried after the delay given by the [`Backoff`].
+    pub fn subscribe<U>(&self, event: &str) -> impl Stream<Item = error::Result<U>>
+    where
+        U: DeserializeOwned,
+    {
+        self.subscribe_with_buffer(event, Buffer::default())
+    }
+
+    /// Subscribes to an event and iterates through its messages across reconnections, buffering them according to `buffer` until they are consumed.
+    /// See [`Client::subscribe_with_buffer`] for details.
+    pub fn subscribe_with_buffer<U>(&self, event: &str, buffer: Buffer) -> impl Stream<Item = error::Result<U>>
+    where
+        U: DeserializeOwned,
+    {
+        self.make_subscription(event, buffer, Packet::into_message)
+    }
+
+    /// Subscribes to an event and iterates through its messages along with how they have been received, across reconnections. See
+    /// [`Client::subscribe_events`] for details.
+    ///
+    /// The sequence numbers start over on every new connection, which is published as [`ConnectionState::Resubscribed`].
+    pub fn subscribe_events<U>(&self, event: &str) -> impl Stream<Item = error::Result<Event<U>>>
+    where
+        U: DeserializeOwned,
+    {
+        self.subscribe_events_with_buffer(event, Buffer::default())
+    }
+
+    /// Subscribes to an event and iterates through its messages along with how they have been received across reconnections, buffering them
+    /// according to `buffer` until they are consumed. See [`Client::subscribe_events_with_buffer`] for details.
+    pub fn subscribe_events_with_buffer<U>(&self, event: &str, buffer: Buffer) -> impl Stream<Item = error::Result<Event<U>>>
+    where
+        U: DeserializeOwned,
+    {
+        self.make_subscription(event, buffer, Event::from_packet)
+    }
+
+    fn make_subscription<U>(&self, event: &str, buffer: Buffer, decode: fn(Packet) -> error::Result<U>) -> impl Stream<Item = error::Result<U>> {
+        let mut clients = self.clients.clone();
+        let states = self.states.clone();
+        let backoff = self.backoff;
+        let event = event.to_string();
+
+        stream! {
+            let mut resubscribing = false;
+            let mut delay = backoff.initial;
+
+            loop {
+                let client = match current(&mut clients).await {
+                    Ok(client) => client,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    },
+                };
+
+                let rx = match client.register(&event, client.timeout(), buffer).await {
+                    Ok(rx) => rx,
+                    Err(e) if e.is_disconnected() || e.is_closed() => continue,
+                    Err(e) if e.is_timeout() => {
+                        time::sleep(delay).await;
+                        delay = (delay * 2).min(backoff.max);
+                        continue;
+                    },
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    },
+                };
+                delay = backoff.initial;
+
+                if resubscribing {
+                    let _ = states.send(ConnectionState::Resubscribed(event.clone()));
+                }
+                resubscribing = true;
+
+                let tolerate_invalid_messages = client.tolerate_invalid_messages;
+                let stream = events(rx, decode, tolerate_invalid_messages);
+                pin_mut!(stream);
+
+                while let Some(item) = stream.next().await {
+                    match item {
+                        Ok(item) => yield Ok(item),
+                        Err(e) if e.is_disconnected() || e.is_closed() => break,
+                        Err(e) if e.is_lagged() || e.is_protocol() => yield Err(e),
+                        Err(e) if tolerate_invalid_messages && e.payload().is_some() => yield Err(e),
+                        Err(e) => {
+                            yield Err(e);
+                            return;
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Connects to the IKE daemon and publishes the client until the connection is closed, over and over again. Failed attempts are published as
+/// [`ConnectionState::ConnectFailed`].
+async fn supervise<F, Fut>(connect: F, backoff: Backoff, clients: Arc<watch::Sender<Option<Client>>>, states: broadcast::Sender<ConnectionState>)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = io::Result<Client>>,
+{
+    loop {
+        let mut delay = backoff.initial;
+        let client = loop {
+            match connect().await {
+                Ok(client) => break client,
+                Err(e) => {
+                    let _ = states.send(ConnectionState::ConnectFailed {
+                        kind: e.kind(),
+                        message: e.to_string(),
+                    });
+                    time::sleep(delay).await;
+                    delay = (delay * 2).min(backoff.max);
+                },
+            }
+        };
+
+        clients.send_replace(Some(client.clone()));
+        let _ = states.send(ConnectionState::Connected);
+
+        client.closed().await;
+
+        clients.send_replace(None);
+        let _ = states.send(ConnectionState::Disconnected);
+    }
+}
+
+/// Waits until the connection is established and returns its client. A client whose connection has been closed is never returned.
+async fn current(clients: &mut watch::Receiver<Option<Client>>) -> error::Result<Client> {
+    let client = clients
+        .wait_for(|client| client.as_ref().is_some_and(Client::is_connected))
+        .await
+        .map_err(|_| Error::data(ErrorCode::ListenerClosed))?;
+
+    Ok(client.clone().unwrap())
+}
+
+impl Drop for SupervisorGuard {
+    fn drop(&mut self) {
+        self.supervisor.abort();
+        self.clients.send_replace(None);
+    }
+}
@@ -1,33 +1,46 @@
-use std::io;
-
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use bytes::BytesMut;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 use super::Packet;
+use crate::error;
+
+/// The writing half of a connection. Each packet is encoded with its length prefix into a buffer reused across packets, and written at once.
+///
+/// Packets are read through [`FramedRead`][], which likewise keeps a buffer that can hold several packets.
+///
+/// [`FramedRead`]: tokio_util::codec::FramedRead
+pub(crate) struct Session<W> {
+    writer: W,
+    buf: BytesMut,
+    broken: bool,
+}
 
-impl Packet {
-    pub async fn send<W>(&self, writer: &mut W) -> io::Result<()>
-    where
-        W: AsyncWrite + Unpin,
-    {
-        let buf = self.serialize()?;
-        let len = buf.len() as u32;
-
-        writer.write_all(&len.to_be_bytes()).await?;
-        writer.write_all(&buf).await?;
-
-        Ok(())
+impl<W> Session<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buf: BytesMut::new(),
+            broken: false,
+        }
     }
 
-    pub async fn receive<R>(reader: &mut R) -> io::Result<Self>
-    where
-        R: AsyncRead + Unpin,
-    {
-        let len = reader.read_u32().await?;
-        let mut buf = vec![0; len as usize];
+    /// Returns true once writing a packet has failed. The connection can no longer be used, since the packet may have been written in part.
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
 
-        reader.read_exact(&mut buf).await?;
-        let packet = Packet::deserialize(&buf)?;
+    /// Writes the packet with its length prefix. Nothing is written if the packet cannot be encoded.
+    pub async fn send(&mut self, packet: &Packet) -> error::Result<()> {
+        self.buf.clear();
+        packet.encode(&mut self.buf)?;
 
-        Ok(packet)
+        if let Err(e) = self.writer.write_all(&self.buf).await {
+            self.broken = true;
+            return Err(e.into());
+        }
+        Ok(())
     }
 }
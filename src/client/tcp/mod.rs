@@ -4,7 +4,7 @@ use std::io;
 
 use tokio::net::{TcpStream, ToSocketAddrs};
 
-use crate::client::Client;
+use crate::client::{Client, ClientOptions};
 
 /// Connects to the IKE daemon via a TCP connection. See [`Client`][] for its usage.
 pub async fn connect<A>(addr: A) -> io::Result<Client>
@@ -14,3 +14,12 @@ where
     let session = TcpStream::connect(&addr).await?;
     Ok(Client::new(session))
 }
+
+/// Connects to the IKE daemon like [`connect`] with the given `options`.
+pub async fn connect_with_options<A>(addr: A, options: ClientOptions) -> io::Result<Client>
+where
+    A: ToSocketAddrs,
+{
+    let session = TcpStream::connect(&addr).await?;
+    Ok(Client::with_options(session, options))
+}
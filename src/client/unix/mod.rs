@@ -4,7 +4,7 @@ use std::{io, path::Path};
 
 use tokio::net::UnixStream;
 
-use crate::client::Client;
+use crate::client::{Client, ClientOptions};
 
 /// Connects to the IKE daemon via a Unix socket named by `path`. See [`Client`][] for its usage.
 pub async fn connect<P>(path: P) -> io::Result<Client>
@@ -14,3 +14,12 @@ where
     let session = UnixStream::connect(&path).await?;
     Ok(Client::new(session))
 }
+
+/// Connects to the IKE daemon like [`connect`] with the given `options`.
+pub async fn connect_with_options<P>(path: P, options: ClientOptions) -> io::Result<Client>
+where
+    P: AsRef<Path>,
+{
+    let session = UnixStream::connect(&path).await?;
+    Ok(Client::with_options(session, options))
+}
@@ -0,0 +1,291 @@
+use std::time::Duration;
+
+use futures_util::{stream::TryStreamExt, Stream};
+use serde::{de::IgnoredAny, Deserialize, Serialize, Serializer};
+
+use crate::{error, Client, StreamResponse};
+
+/// A message of the `control-log` event, which is streamed while the IKE daemon is processing a command that controls SAs.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct ControlLog {
+    /// The subsystem that has issued the message, such as `IKE` or `CFG`.
+    pub group: String,
+
+    /// The log level of the message, from 0 to 4.
+    pub level: i32,
+
+    /// The name of the IKE_SA if the message is associated with any.
+    #[serde(rename = "ikesa-name")]
+    pub ikesa_name: Option<String>,
+
+    /// The unique identifier of the IKE_SA if the message is associated with any.
+    #[serde(rename = "ikesa-uniqueid")]
+    pub ikesa_uniqueid: Option<u32>,
+
+    /// The text of the message.
+    pub msg: String,
+}
+
+/// How long the IKE daemon waits for a command that controls SAs to complete before responding. The command goes on in the background after the
+/// response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timeout {
+    /// Responds right away without waiting for the command to complete.
+    Immediate,
+
+    /// Waits up to the given duration, which is sent in milliseconds. It is rounded up to at least one millisecond, since zero makes the daemon wait
+    /// indefinitely, and capped at the largest value the daemon accepts.
+    After(Duration),
+}
+
+impl From<Duration> for Timeout {
+    fn from(duration: Duration) -> Self {
+        Self::After(duration)
+    }
+}
+
+impl Serialize for Timeout {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Immediate => serializer.serialize_i32(-1),
+            Self::After(duration) => {
+                let millis = duration.as_nanos().div_ceil(1_000_000).clamp(1, i32::MAX as u128);
+                serializer.serialize_i32(millis as i32)
+            },
+        }
+    }
+}
+
+/// The request of the `initiate` command. At least `child` or `ike` has to be given.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct InitiateOptions {
+    /// The name of the CHILD_SA configuration to initiate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub child: Option<String>,
+
+    /// The name of the IKE_SA configuration to initiate, or to find the CHILD_SA configuration under.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ike: Option<String>,
+
+    /// How long the IKE daemon waits for the initiation to complete before responding. The daemon waits indefinitely if this is not given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Timeout>,
+
+    /// Whether the limits of the IKE daemon, such as the number of half-open IKE_SAs, may prevent the initiation.
+    #[serde(rename = "init-limits", skip_serializing_if = "Option::is_none")]
+    pub init_limits: Option<bool>,
+
+    /// The log level up to which the messages of the `control-log` event are streamed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loglevel: Option<i32>,
+}
+
+/// The result of a successful `initiate` command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InitiateOutcome {
+    /// The unique identifier of the IKE_SA, which is taken from the first message of the `control-log` event associated with an IKE_SA.
+    pub ikesa_uniqueid: Option<u32>,
+}
+
+/// The SAs a command applies to, which are matched by the names of their configurations or their unique identifiers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SaSelector {
+    /// The name of the CHILD_SA configuration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub child: Option<String>,
+
+    /// The name of the IKE_SA configuration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ike: Option<String>,
+
+    /// The unique identifier of the CHILD_SA.
+    #[serde(rename = "child-id", skip_serializing_if = "Option::is_none")]
+    pub child_id: Option<u32>,
+
+    /// The unique identifier of the IKE_SA.
+    #[serde(rename = "ike-id", skip_serializing_if = "Option::is_none")]
+    pub ike_id: Option<u32>,
+}
+
+/// The IKE_SAs a command applies to, which are matched by the names of their configurations or their unique identifiers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct IkeSaSelector {
+    /// The name of the IKE_SA configuration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ike: Option<String>,
+
+    /// The unique identifier of the IKE_SA.
+    #[serde(rename = "ike-id", skip_serializing_if = "Option::is_none")]
+    pub ike_id: Option<u32>,
+}
+
+/// The request of the `terminate` command.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TerminateOptions {
+    /// The SAs to terminate.
+    #[serde(flatten)]
+    pub selector: SaSelector,
+
+    /// Whether to terminate the IKE_SA without waiting for the DELETE to be acknowledged. With `timeout`, the IKE daemon waits for the
+    /// acknowledgement until the timeout is reached.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force: Option<bool>,
+
+    /// How long the IKE daemon waits for the termination to complete before responding. The daemon waits indefinitely if this is not given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Timeout>,
+
+    /// The log level up to which the messages of the `control-log` event are streamed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loglevel: Option<i32>,
+}
+
+/// The result of a successful `terminate` command.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct TerminateOutcome {
+    /// The number of SAs matched by the selector.
+    #[serde(default)]
+    pub matches: u32,
+
+    /// The number of SAs terminated.
+    #[serde(default)]
+    pub terminated: u32,
+}
+
+/// The request of the `rekey` command.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RekeyOptions {
+    /// The SAs to rekey.
+    #[serde(flatten)]
+    pub selector: SaSelector,
+
+    /// Whether to reauthenticate the IKE_SA instead of rekeying it, which only applies to IKEv2.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reauth: Option<bool>,
+}
+
+/// The request of the `redirect` command. Create it with [`RedirectOptions::new`], since the gateway has to be given.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RedirectOptions {
+    /// The IKE_SAs to redirect.
+    #[serde(flatten)]
+    pub selector: IkeSaSelector,
+
+    /// The IP address of the peers whose IKE_SAs are redirected, which can also be a subnet or range.
+    #[serde(rename = "peer-ip", skip_serializing_if = "Option::is_none")]
+    pub peer_ip: Option<String>,
+
+    /// The identity of the peers whose IKE_SAs are redirected.
+    #[serde(rename = "peer-id", skip_serializing_if = "Option::is_none")]
+    pub peer_id: Option<String>,
+
+    /// The IP address or hostname of the gateway to redirect the peers to.
+    pub gateway: String,
+}
+
+impl RedirectOptions {
+    /// Creates a request that redirects IKE_SAs to `gateway`. The IKE_SAs are narrowed down by setting the other fields.
+    pub fn new(gateway: impl Into<String>) -> Self {
+        Self {
+            selector: IkeSaSelector::default(),
+            peer_ip: None,
+            peer_id: None,
+            gateway: gateway.into(),
+        }
+    }
+}
+
+/// The result of a successful `rekey` or `redirect` command.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct ControlOutcome {
+    /// The number of SAs matched by the selector.
+    #[serde(default)]
+    pub matches: u32,
+}
+
+impl Client {
+    /// Initiates a CHILD_SA or IKE_SA using the `initiate` command, and iterates through the messages of the `control-log` event issued during the
+    /// initiation, followed by its outcome.
+    ///
+    /// A failure to initiate the SA fails the stream with an error categorized as [`Category::CmdFailure`]. The unique identifier of the IKE_SA is
+    /// then only available from the messages yielded before. See [`Client::stream_request_with_response`] for the other details.
+    ///
+    /// # Example
+    #[cfg_attr(unix, doc = "```no_run")]
+    #[cfg_attr(not(unix), doc = "```ignore")]
+    /// use std::{error::Error, time::Duration};
+    ///
+    /// use futures_util::{
+    ///     stream::TryStreamExt,
+    ///     pin_mut,
+    /// };
+    /// use rsvici::{
+    ///     command::{InitiateOptions, Timeout},
+    ///     StreamResponse,
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn Error>> {
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///
+    ///     let options = InitiateOptions {
+    ///         child: Some("net-net".to_string()),
+    ///         timeout: Some(Timeout::After(Duration::from_secs(10))),
+    ///         ..Default::default()
+    ///     };
+    ///
+    ///     let initiation = client.initiate(options);
+    ///     pin_mut!(initiation);
+    ///
+    ///     while let Some(response) = initiation.try_next().await? {
+    ///         match response {
+    ///             StreamResponse::Event(log) => println!("[{}] {}", log.group, log.msg),
+    ///             StreamResponse::Response(outcome) => println!("Outcome: {:#?}", outcome),
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [`Category::CmdFailure`]: crate::error::Category::CmdFailure
+    pub fn initiate(&self, options: InitiateOptions) -> impl Stream<Item = error::Result<StreamResponse<ControlLog, InitiateOutcome>>> {
+        let mut ikesa_uniqueid = None;
+
+        self.stream_request_with_response::<_, ControlLog, IgnoredAny>("initiate", "control-log", options)
+            .map_ok(move |response| match response {
+                StreamResponse::Event(log) => {
+                    ikesa_uniqueid = ikesa_uniqueid.or(log.ikesa_uniqueid);
+                    StreamResponse::Event(log)
+                },
+                StreamResponse::Response(_) => StreamResponse::Response(InitiateOutcome { ikesa_uniqueid }),
+            })
+    }
+
+    /// Terminates CHILD_SAs or IKE_SAs using the `terminate` command, and iterates through the messages of the `control-log` event issued during the
+    /// termination, followed by its outcome.
+    ///
+    /// See [`Client::initiate`] for details.
+    pub fn terminate(&self, options: TerminateOptions) -> impl Stream<Item = error::Result<StreamResponse<ControlLog, TerminateOutcome>>> {
+        self.stream_request_with_response("terminate", "control-log", options)
+    }
+
+    /// Rekeys CHILD_SAs or IKE_SAs using the `rekey` command. A failure to rekey the SAs is returned as an error categorized as
+    /// [`Category::CmdFailure`].
+    ///
+    /// [`Category::CmdFailure`]: crate::error::Category::CmdFailure
+    pub async fn rekey(&self, options: RekeyOptions) -> error::Result<ControlOutcome> {
+        self.make_request("rekey", options, self.timeout(), true).await
+    }
+
+    /// Redirects IKE_SAs to another gateway using the `redirect` command, which is only supported by IKEv2. A failure to redirect the SAs is returned
+    /// as an error categorized as [`Category::CmdFailure`].
+    ///
+    /// [`Category::CmdFailure`]: crate::error::Category::CmdFailure
+    pub async fn redirect(&self, options: RedirectOptions) -> error::Result<ControlOutcome> {
+        self.make_request("redirect", options, self.timeout(), true).await
+    }
+}
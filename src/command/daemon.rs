@@ -0,0 +1,147 @@
+use serde::Deserialize;
+
+use crate::{client::Response, error, Client};
+
+/// The response to the `version` command, which describes the IKE daemon and the system it runs on.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Version {
+    /// The name of the IKE daemon, such as `charon`.
+    pub daemon: String,
+
+    /// The version of strongSwan.
+    pub version: String,
+
+    /// The name of the operating system.
+    pub sysname: String,
+
+    /// The release of the operating system.
+    pub release: String,
+
+    /// The hardware identifier.
+    pub machine: String,
+}
+
+/// The response to the `stats` command, which describes the state of the IKE daemon.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Stats {
+    /// How long the IKE daemon has been running.
+    pub uptime: Uptime,
+
+    /// The worker threads of the IKE daemon.
+    pub workers: Workers,
+
+    /// The number of jobs queued for each priority.
+    pub queues: Priorities,
+
+    /// The number of jobs scheduled for timed execution.
+    pub scheduled: u32,
+
+    /// The number of IKE_SAs.
+    pub ikesas: IkeSaCount,
+
+    /// The names of the loaded plugins.
+    pub plugins: Vec<String>,
+
+    /// The usage of the heap memory, which is only available if strongSwan is built with leak-detective or runs on Windows.
+    pub mem: Option<Memory>,
+
+    /// The statistics of the memory allocator, which are only available with `mallinfo()` support.
+    pub mallinfo: Option<Mallinfo>,
+}
+
+/// How long the IKE daemon has been running.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Uptime {
+    /// The relative uptime in a human-readable form, such as `5 minutes`.
+    pub running: String,
+
+    /// The absolute time at which the IKE daemon has been started, such as `Oct 16 12:00:00 2026`.
+    pub since: String,
+}
+
+/// The worker threads of the IKE daemon.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Workers {
+    /// The total number of worker threads.
+    pub total: u32,
+
+    /// The number of worker threads currently idle.
+    pub idle: u32,
+
+    /// The number of worker threads processing jobs of each priority.
+    pub active: Priorities,
+}
+
+/// The number of threads or jobs for each priority of jobs.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Priorities {
+    /// The number for the jobs of the critical priority.
+    pub critical: u32,
+
+    /// The number for the jobs of the high priority.
+    pub high: u32,
+
+    /// The number for the jobs of the medium priority.
+    pub medium: u32,
+
+    /// The number for the jobs of the low priority.
+    pub low: u32,
+}
+
+/// The number of IKE_SAs.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct IkeSaCount {
+    /// The total number of active IKE_SAs.
+    pub total: u32,
+
+    /// The number of IKE_SAs in the half-open state.
+    #[serde(rename = "half-open")]
+    pub half_open: u32,
+}
+
+/// The usage of the heap memory.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Memory {
+    /// The total usage of the heap memory in bytes.
+    pub total: u64,
+
+    /// The total number of allocated blocks.
+    pub allocs: u64,
+}
+
+/// The statistics of the memory allocator as reported by `mallinfo()`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Mallinfo {
+    /// The non-mmapped space allocated in bytes.
+    pub sbrk: u64,
+
+    /// The mmapped space allocated in bytes.
+    pub mmap: u64,
+
+    /// The total number of bytes used.
+    pub used: u64,
+
+    /// The number of bytes available but unused.
+    pub free: u64,
+}
+
+impl Client {
+    /// Returns the version of the IKE daemon and the system it runs on, using the `version` command.
+    pub async fn version(&self) -> error::Result<Version> {
+        self.request("version", ()).await
+    }
+
+    /// Returns the statistics of the IKE daemon, using the `stats` command.
+    pub async fn stats(&self) -> error::Result<Stats> {
+        self.request("stats", ()).await
+    }
+
+    /// Reloads the settings of the IKE daemon, using the `reload-settings` command. The call fails with an error categorized as
+    /// [`Category::CmdFailure`] if the daemon cannot reload them.
+    ///
+    /// [`Category::CmdFailure`]: crate::error::Category::CmdFailure
+    pub async fn reload_settings(&self) -> error::Result<()> {
+        let response: Response = self.request("reload-settings", ()).await?;
+        response.check()
+    }
+}
@@ -0,0 +1,137 @@
+//! Commands of the VICI protocol bound to the types of their requests and responses.
+//!
+//! The models of the common commands are provided along with the methods of [`Client`] that call them, such as [`Client::version`] and
+//! [`Client::stats`]. Other commands can be bound to their own types as follows.
+//!
+//! A request message that implements [`Command`] knows the name of its command and the type of its response, so that [`Client::call`] infers both from
+//! the message alone. A message that also implements [`StreamCommand`] knows the event streamed for the command and the type of its messages, which
+//! [`Client::call_stream`] iterates through. Passing a message to the wrong method, or expecting a response of another type, fails to compile.
+//!
+//! Both traits are usually implemented with `#[derive(ViciCommand)]`, which takes the following arguments in `#[vici(...)]`:
+//!
+//! - `command = "..."`: the name of the command, which is required.
+//! - `response = Type`: the type of the response, which defaults to `()`.
+//! - `event = "..."`: the name of the event streamed for the command.
+//! - `item = Type`: the type of the messages of the event, which is required along with `event`.
+//!
+//! # Example
+#![cfg_attr(all(unix, feature = "derive"), doc = "```no_run")]
+#![cfg_attr(not(all(unix, feature = "derive")), doc = "```ignore")]
+//! use std::{collections::HashMap, error::Error};
+//!
+//! use futures_util::{
+//!     stream::TryStreamExt,
+//!     pin_mut,
+//! };
+//! use rsvici::ViciCommand;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct Pool {
+//!     base: String,
+//!     size: u32,
+//!     online: u32,
+//!     offline: u32,
+//! }
+//!
+//! #[derive(Serialize, ViciCommand)]
+//! #[vici(command = "get-pools", response = HashMap<String, Pool>)]
+//! struct GetPools;
+//!
+//! #[derive(Serialize, ViciCommand)]
+//! #[vici(command = "list-conns", event = "list-conn", item = rsvici::Value)]
+//! struct ListConns<'a> {
+//!     ike: Option<&'a str>,
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn Error>> {
+//!     let client = rsvici::unix::connect("/run/charon.vici").await?;
+//!
+//!     let pools = client.call(GetPools).await?;
+//!     println!("Pools: {:#?}", pools);
+//!
+//!     let conns = client.call_stream(ListConns { ike: Some("gw-gw") });
+//!     pin_mut!(conns);
+//!
+//!     while let Some(conn) = conns.try_next().await? {
+//!         println!("{:#?}", conn);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! A message that does not stream an event cannot be passed to [`Client::call_stream`]:
+//!
+#![cfg_attr(feature = "derive", doc = "```compile_fail,E0277")]
+#![cfg_attr(not(feature = "derive"), doc = "```ignore")]
+//! use futures_util::stream::TryStreamExt;
+//! use rsvici::{Client, ViciCommand};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize, ViciCommand)]
+//! #[vici(command = "version", response = rsvici::command::Version)]
+//! struct GetVersion;
+//!
+//! async fn run(client: &Client) -> rsvici::error::Result<()> {
+//!     let _ = client.call_stream(GetVersion).try_collect::<Vec<_>>().await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Nor can its response be taken as another type:
+//!
+#![cfg_attr(feature = "derive", doc = "```compile_fail,E0308")]
+#![cfg_attr(not(feature = "derive"), doc = "```ignore")]
+//! use rsvici::{command::Stats, Client, ViciCommand};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize, ViciCommand)]
+//! #[vici(command = "version", response = rsvici::command::Version)]
+//! struct GetVersion;
+//!
+//! async fn run(client: &Client) -> rsvici::error::Result<()> {
+//!     let _: Stats = client.call(GetVersion).await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`Client`]: crate::Client
+//! [`Client::version`]: crate::Client::version
+//! [`Client::stats`]: crate::Client::stats
+//! [`Client::call`]: crate::Client::call
+//! [`Client::call_stream`]: crate::Client::call_stream
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub use self::{
+    control::{
+        ControlLog, ControlOutcome, IkeSaSelector, InitiateOptions, InitiateOutcome, RedirectOptions, RekeyOptions, SaSelector, TerminateOptions,
+        TerminateOutcome, Timeout,
+    },
+    daemon::{IkeSaCount, Mallinfo, Memory, Priorities, Stats, Uptime, Version, Workers},
+    sa::{ChildSa, ChildSaState, IkeSa, IkeSaState, IpsecMode, IpsecProtocol, ListSasOptions},
+};
+
+mod control;
+mod daemon;
+mod sa;
+
+/// A request message of a command, which determines the name of the command and the type of its response.
+pub trait Command: Serialize {
+    /// The name of the command.
+    const NAME: &'static str;
+
+    /// The type of the response to the command.
+    type Response: DeserializeOwned;
+}
+
+/// A request message of a command that streams an event, which determines the name of the event and the type of its messages.
+pub trait StreamCommand: Command {
+    /// The name of the event streamed for the command.
+    const EVENT: &'static str;
+
+    /// The type of the messages of the event.
+    type Item: DeserializeOwned;
+}
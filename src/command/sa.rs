@@ -0,0 +1,438 @@
+use futures_util::{
+    stream::{self, TryStreamExt},
+    Stream,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{error, Client};
+
+/// The request of the `list-sas` command.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ListSasOptions {
+    /// Whether to skip the SAs that are in use by other threads instead of waiting for them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub noblock: Option<bool>,
+
+    /// The name of the IKE_SA configuration to list the IKE_SAs of.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ike: Option<String>,
+
+    /// The unique identifier of the IKE_SA to list.
+    #[serde(rename = "ike-id", skip_serializing_if = "Option::is_none")]
+    pub ike_id: Option<u32>,
+}
+
+/// An IKE_SA listed by the `list-sas` command.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct IkeSa {
+    /// The name of the IKE_SA configuration.
+    #[serde(skip)]
+    pub name: String,
+
+    /// The unique identifier of the IKE_SA.
+    pub uniqueid: u32,
+
+    /// The version of IKE, which is either 1 or 2.
+    pub version: u8,
+
+    /// The state of the IKE_SA.
+    pub state: IkeSaState,
+
+    /// The address of the local endpoint.
+    pub local_host: Option<String>,
+
+    /// The port of the local endpoint.
+    pub local_port: Option<u16>,
+
+    /// The local identity.
+    pub local_id: Option<String>,
+
+    /// The address of the remote endpoint.
+    pub remote_host: Option<String>,
+
+    /// The port of the remote endpoint.
+    pub remote_port: Option<u16>,
+
+    /// The remote identity.
+    pub remote_id: Option<String>,
+
+    /// The remote XAuth identity if the peer has been authenticated with XAuth.
+    pub remote_xauth_id: Option<String>,
+
+    /// The remote EAP identity if the peer has been authenticated with EAP.
+    pub remote_eap_id: Option<String>,
+
+    /// Whether this end is the initiator of the IKE_SA.
+    #[serde(default)]
+    pub initiator: bool,
+
+    /// The SPI or cookie of the initiator in hex.
+    pub initiator_spi: String,
+
+    /// The SPI or cookie of the responder in hex.
+    pub responder_spi: String,
+
+    /// Whether the local endpoint is behind a NAT.
+    #[serde(default)]
+    pub nat_local: bool,
+
+    /// Whether the remote endpoint is behind a NAT.
+    #[serde(default)]
+    pub nat_remote: bool,
+
+    /// Whether a NAT has been faked as the responder.
+    #[serde(default)]
+    pub nat_fake: bool,
+
+    /// Whether any endpoint is behind a NAT, including a faked one.
+    #[serde(default)]
+    pub nat_any: bool,
+
+    /// The default inbound XFRM interface ID in hex.
+    pub if_id_in: Option<String>,
+
+    /// The default outbound XFRM interface ID in hex.
+    pub if_id_out: Option<String>,
+
+    /// The encryption algorithm.
+    pub encr_alg: Option<String>,
+
+    /// The key size of the encryption algorithm if applicable.
+    pub encr_keysize: Option<u32>,
+
+    /// The integrity algorithm.
+    pub integ_alg: Option<String>,
+
+    /// The key size of the integrity algorithm if applicable.
+    pub integ_keysize: Option<u32>,
+
+    /// The pseudo random function.
+    pub prf_alg: Option<String>,
+
+    /// The Diffie-Hellman group.
+    pub dh_group: Option<String>,
+
+    /// Whether a PPK has been used.
+    #[serde(default)]
+    pub ppk: bool,
+
+    /// The seconds since the IKE_SA has been established.
+    pub established: Option<u64>,
+
+    /// The seconds before the IKE_SA gets rekeyed.
+    pub rekey_time: Option<u64>,
+
+    /// The seconds before the IKE_SA gets reauthenticated.
+    pub reauth_time: Option<u64>,
+
+    /// The virtual IPs assigned by the remote peer and installed locally.
+    #[serde(default)]
+    pub local_vips: Vec<String>,
+
+    /// The virtual IPs assigned to the remote peer.
+    #[serde(default)]
+    pub remote_vips: Vec<String>,
+
+    /// The tasks queued for execution.
+    #[serde(default)]
+    pub tasks_queued: Vec<String>,
+
+    /// The tasks being initiated actively.
+    #[serde(default)]
+    pub tasks_active: Vec<String>,
+
+    /// The tasks being handled passively.
+    #[serde(default)]
+    pub tasks_passive: Vec<String>,
+
+    /// The CHILD_SAs of the IKE_SA, keyed by their unique names.
+    #[serde(default)]
+    pub child_sas: IndexMap<String, ChildSa>,
+}
+
+/// A CHILD_SA listed by the `list-sas` command.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ChildSa {
+    /// The name of the CHILD_SA configuration.
+    pub name: String,
+
+    /// The unique identifier of the CHILD_SA.
+    pub uniqueid: u32,
+
+    /// The reqid of the CHILD_SA.
+    pub reqid: u32,
+
+    /// The state of the CHILD_SA.
+    pub state: ChildSaState,
+
+    /// The IPsec mode.
+    pub mode: IpsecMode,
+
+    /// The IPsec protocol.
+    pub protocol: IpsecProtocol,
+
+    /// Whether UDP encapsulation is used.
+    #[serde(default)]
+    pub encap: bool,
+
+    /// The inbound SPI in hex.
+    pub spi_in: Option<String>,
+
+    /// The outbound SPI in hex.
+    pub spi_out: Option<String>,
+
+    /// The inbound CPI in hex if compression is used.
+    pub cpi_in: Option<String>,
+
+    /// The outbound CPI in hex if compression is used.
+    pub cpi_out: Option<String>,
+
+    /// The inbound Netfilter mark value in hex.
+    pub mark_in: Option<String>,
+
+    /// The inbound Netfilter mark mask in hex.
+    pub mark_mask_in: Option<String>,
+
+    /// The outbound Netfilter mark value in hex.
+    pub mark_out: Option<String>,
+
+    /// The outbound Netfilter mark mask in hex.
+    pub mark_mask_out: Option<String>,
+
+    /// The inbound XFRM interface ID in hex.
+    pub if_id_in: Option<String>,
+
+    /// The outbound XFRM interface ID in hex.
+    pub if_id_out: Option<String>,
+
+    /// The encryption algorithm if any.
+    pub encr_alg: Option<String>,
+
+    /// The key size of the encryption algorithm if applicable.
+    pub encr_keysize: Option<u32>,
+
+    /// The integrity algorithm if any.
+    pub integ_alg: Option<String>,
+
+    /// The key size of the integrity algorithm if applicable.
+    pub integ_keysize: Option<u32>,
+
+    /// The pseudo random function.
+    pub prf_alg: Option<String>,
+
+    /// The Diffie-Hellman group for PFS rekeying if any.
+    pub dh_group: Option<String>,
+
+    /// Whether extended sequence numbers are used.
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub esn: bool,
+
+    /// The number of inbound bytes processed.
+    #[serde(default)]
+    pub bytes_in: u64,
+
+    /// The number of inbound packets processed.
+    #[serde(default)]
+    pub packets_in: u64,
+
+    /// The seconds since the last inbound packet if any.
+    pub use_in: Option<u64>,
+
+    /// The number of outbound bytes processed.
+    #[serde(default)]
+    pub bytes_out: u64,
+
+    /// The number of outbound packets processed.
+    #[serde(default)]
+    pub packets_out: u64,
+
+    /// The seconds since the last outbound packet if any.
+    pub use_out: Option<u64>,
+
+    /// The seconds before the CHILD_SA gets rekeyed.
+    pub rekey_time: Option<u64>,
+
+    /// The seconds before the CHILD_SA expires.
+    pub life_time: Option<u64>,
+
+    /// The seconds since the CHILD_SA has been installed.
+    pub install_time: Option<u64>,
+
+    /// The local traffic selectors.
+    #[serde(default)]
+    pub local_ts: Vec<String>,
+
+    /// The remote traffic selectors.
+    #[serde(default)]
+    pub remote_ts: Vec<String>,
+}
+
+/// The state of an IKE_SA.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum IkeSaState {
+    /// The IKE_SA has just been created.
+    Created,
+
+    /// The IKE_SA is being established.
+    Connecting,
+
+    /// The IKE_SA has been established.
+    Established,
+
+    /// The IKE_SA is only kept passively, e.g. for an HA cluster.
+    Passive,
+
+    /// The IKE_SA is being rekeyed.
+    Rekeying,
+
+    /// The IKE_SA has been rekeyed and is about to be deleted.
+    Rekeyed,
+
+    /// The IKE_SA is being deleted.
+    Deleting,
+
+    /// The IKE_SA is being destroyed.
+    Destroying,
+
+    /// A state unknown to this library.
+    #[serde(other)]
+    Unknown,
+}
+
+/// The state of a CHILD_SA.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ChildSaState {
+    /// The CHILD_SA has just been created.
+    Created,
+
+    /// The CHILD_SA has been routed to be established on demand.
+    Routed,
+
+    /// The CHILD_SA is being installed.
+    Installing,
+
+    /// The CHILD_SA has been installed.
+    Installed,
+
+    /// The CHILD_SA is being updated.
+    Updating,
+
+    /// The CHILD_SA is being rekeyed.
+    Rekeying,
+
+    /// The CHILD_SA has been rekeyed and is about to be deleted.
+    Rekeyed,
+
+    /// The CHILD_SA is being retried after a failure.
+    Retrying,
+
+    /// The CHILD_SA is being deleted.
+    Deleting,
+
+    /// The CHILD_SA has been deleted.
+    Deleted,
+
+    /// The CHILD_SA is being destroyed.
+    Destroying,
+
+    /// A state unknown to this library.
+    #[serde(other)]
+    Unknown,
+}
+
+/// The IPsec mode of a CHILD_SA.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum IpsecMode {
+    /// The transport mode.
+    Transport,
+
+    /// The tunnel mode.
+    Tunnel,
+
+    /// The Bound End-to-End Tunnel mode.
+    Beet,
+
+    /// A shunt policy that passes the traffic.
+    Pass,
+
+    /// A shunt policy that drops the traffic.
+    Drop,
+
+    /// A mode unknown to this library.
+    #[serde(other)]
+    Unknown,
+}
+
+/// The IPsec protocol of a CHILD_SA.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum IpsecProtocol {
+    /// Authentication Header.
+    Ah,
+
+    /// Encapsulating Security Payload.
+    Esp,
+
+    /// A protocol unknown to this library.
+    #[serde(other)]
+    Unknown,
+}
+
+impl Client {
+    /// Lists the IKE_SAs along with their CHILD_SAs using the `list-sas` command, and iterates through them.
+    ///
+    /// See [`Client::stream_request`] for the other details.
+    ///
+    /// # Example
+    #[cfg_attr(unix, doc = "```no_run")]
+    #[cfg_attr(not(unix), doc = "```ignore")]
+    /// use std::error::Error;
+    ///
+    /// use futures_util::{
+    ///     stream::TryStreamExt,
+    ///     pin_mut,
+    /// };
+    /// use rsvici::command::{IkeSaState, ListSasOptions};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn Error>> {
+    ///     let client = rsvici::unix::connect("/run/charon.vici").await?;
+    ///
+    ///     let sas = client.list_sas(ListSasOptions::default());
+    ///     pin_mut!(sas);
+    ///
+    ///     while let Some(sa) = sas.try_next().await? {
+    ///         if sa.state == IkeSaState::Established {
+    ///             println!("{}[{}]: {} CHILD_SAs", sa.name, sa.uniqueid, sa.child_sas.len());
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn list_sas(&self, options: ListSasOptions) -> impl Stream<Item = error::Result<IkeSa>> {
+        self.stream_request::<_, IndexMap<String, IkeSa>>("list-sas", "list-sa", options)
+            .map_ok(|sas| {
+                stream::iter(sas.into_iter().map(|(name, mut sa)| {
+                    sa.name = name;
+                    Ok(sa)
+                }))
+            })
+            .try_flatten()
+    }
+}
+
+/// Deserializes a flag that is either `1` or `yes` if it is set.
+fn deserialize_flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let flag = String::deserialize(deserializer)?;
+    Ok(flag == "1" || flag == "yes")
+}
@@ -7,6 +7,8 @@ use std::{
     io,
 };
 
+use bytes::Bytes;
+
 /// A structure representing all possible errors in rsvici.
 pub struct Error {
     err: Box<ErrorImpl>,
@@ -28,10 +30,14 @@ impl Error {
     /// - `Category::CmdFailure` - failure to execute a command
     /// - `Category::UnknownCmd` - an unknown command request
     /// - `Category::UnknownEvent` - an unknown event request
+    /// - `Category::Timeout` - no response within the timeout
+    /// - `Category::Disconnected` - the connection to the IKE daemon has been closed
+    /// - `Category::Lagged` - a subscriber has dropped messages
+    /// - `Category::Protocol` - a violation of the limits of the protocol
     pub fn classify(&self) -> Category {
         match self.err.code {
             ErrorCode::Io(_) => Category::Io,
-            ErrorCode::InvalidData(_) | ErrorCode::UnexpectedPacket(_) => Category::Data,
+            ErrorCode::InvalidData(_) | ErrorCode::InvalidMessage(..) | ErrorCode::UnexpectedPacket(_) => Category::Data,
             ErrorCode::ListenerClosed
             | ErrorCode::HandlerClosedWhileCommandRequest
             | ErrorCode::HandlerClosedWhileEventRequest(_)
@@ -39,6 +45,15 @@ impl Error {
             ErrorCode::CommandFailed(_) => Category::CmdFailure,
             ErrorCode::UnknownCmd => Category::UnknownCmd,
             ErrorCode::UnknownEvent(_) => Category::UnknownEvent,
+            ErrorCode::Timeout => Category::Timeout,
+            ErrorCode::Disconnected => Category::Disconnected,
+            ErrorCode::Lagged(_) => Category::Lagged,
+            ErrorCode::FrameTooLarge(..)
+            | ErrorCode::NameTooLong(_)
+            | ErrorCode::PacketTooLarge(_)
+            | ErrorCode::UnknownPacketType(..)
+            | ErrorCode::TruncatedPacket(_)
+            | ErrorCode::InvalidName(..) => Category::Protocol,
         }
     }
 
@@ -72,6 +87,54 @@ impl Error {
         self.classify() == Category::UnknownEvent
     }
 
+    /// Returns true if this error was caused by no response within the timeout.
+    pub fn is_timeout(&self) -> bool {
+        self.classify() == Category::Timeout
+    }
+
+    /// Returns true if this error was caused by the connection to the IKE daemon being closed.
+    pub fn is_disconnected(&self) -> bool {
+        self.classify() == Category::Disconnected
+    }
+
+    /// Returns true if this error was caused by a subscriber dropping messages.
+    pub fn is_lagged(&self) -> bool {
+        self.classify() == Category::Lagged
+    }
+
+    /// Returns true if this error was caused by a violation of the limits of the protocol.
+    pub fn is_protocol(&self) -> bool {
+        self.classify() == Category::Protocol
+    }
+
+    /// Returns the number of messages a subscriber has dropped if this error was caused by it.
+    pub fn lost_messages(&self) -> Option<u64> {
+        match self.err.code {
+            ErrorCode::Lagged(lost) => Some(lost),
+            _ => None,
+        }
+    }
+
+    /// Returns the raw bytes of the packet that failed to be decoded, starting with its type, if this error was caused by it.
+    pub fn raw_packet(&self) -> Option<&[u8]> {
+        match self.err.code {
+            ErrorCode::UnknownPacketType(_, ref bytes) | ErrorCode::TruncatedPacket(ref bytes) | ErrorCode::InvalidName(_, ref bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the encoded payload of the message that failed to be deserialized if this error was caused by it.
+    ///
+    /// The payload is in the format of the VICI protocol; see [`rsvici::raw`] for details.
+    ///
+    /// [`rsvici::raw`]: crate::raw
+    pub fn payload(&self) -> Option<&[u8]> {
+        match self.err.code {
+            ErrorCode::InvalidMessage(_, ref payload) => Some(payload),
+            _ => None,
+        }
+    }
+
     pub(crate) fn io(e: io::Error) -> Self {
         Self {
             err: Box::new(ErrorImpl { code: ErrorCode::Io(e) }),
@@ -105,6 +168,18 @@ pub enum Category {
 
     /// The error was caused by an unknown event request.
     UnknownEvent,
+
+    /// The error was caused by no response within the timeout.
+    Timeout,
+
+    /// The error was caused by the connection to the IKE daemon being closed.
+    Disconnected,
+
+    /// The error was caused by a subscriber dropping messages.
+    Lagged,
+
+    /// The error was caused by a violation of the limits of the protocol.
+    Protocol,
 }
 
 impl From<io::Error> for Error {
@@ -128,6 +203,10 @@ impl From<Error> for io::Error {
             Category::Closed => io::Error::new(io::ErrorKind::BrokenPipe, e),
             Category::CmdFailure => io::Error::other(e),
             Category::UnknownCmd | Category::UnknownEvent => io::Error::new(io::ErrorKind::Unsupported, e),
+            Category::Timeout => io::Error::new(io::ErrorKind::TimedOut, e),
+            Category::Disconnected => io::Error::new(io::ErrorKind::ConnectionAborted, e),
+            Category::Lagged => io::Error::other(e),
+            Category::Protocol => io::Error::new(io::ErrorKind::InvalidData, e),
         }
     }
 }
@@ -145,6 +224,9 @@ pub(crate) enum ErrorCode {
     /// Invalid data when serializing/deserializing payload.
     InvalidData(serde_vici::Error),
 
+    /// Message that has been received failed to be deserialized.
+    InvalidMessage(serde_vici::Error, Bytes),
+
     /// Listener has already been closed.
     ListenerClosed,
 
@@ -168,6 +250,33 @@ pub(crate) enum ErrorCode {
 
     /// Unknown event has been requested.
     UnknownEvent(String),
+
+    /// No response has been received within the timeout.
+    Timeout,
+
+    /// Connection has been closed.
+    Disconnected,
+
+    /// Subscriber has dropped the given number of messages.
+    Lagged(u64),
+
+    /// Packet of the given size exceeds the maximum frame size.
+    FrameTooLarge(usize, usize),
+
+    /// Name of the packet does not fit in its length prefix.
+    NameTooLong(String),
+
+    /// Packet of the given size does not fit in its length prefix.
+    PacketTooLarge(usize),
+
+    /// Packet of an unknown type has been received.
+    UnknownPacketType(u8, Vec<u8>),
+
+    /// Packet has ended before its name.
+    TruncatedPacket(Vec<u8>),
+
+    /// Packet with a name that is not valid UTF-8 has been received.
+    InvalidName(u8, Vec<u8>),
 }
 
 impl Display for ErrorCode {
@@ -175,6 +284,7 @@ impl Display for ErrorCode {
         match *self {
             ErrorCode::Io(ref err) => Display::fmt(err, f),
             ErrorCode::InvalidData(ref err) => Display::fmt(err, f),
+            ErrorCode::InvalidMessage(ref err, _) => Display::fmt(err, f),
             ErrorCode::ListenerClosed => f.write_str("listener has been closed"),
             ErrorCode::HandlerClosedWhileCommandRequest => f.write_str("handler has been closed while processing command request"),
             ErrorCode::HandlerClosedWhileEventRequest(ref event) => f.write_fmt(format_args!("handler has been closed while processing event: {event}")),
@@ -186,6 +296,15 @@ impl Display for ErrorCode {
             },
             ErrorCode::UnknownCmd => f.write_str("unknown command"),
             ErrorCode::UnknownEvent(ref event) => f.write_fmt(format_args!("unknown event {event}")),
+            ErrorCode::Timeout => f.write_str("timed out waiting for response"),
+            ErrorCode::Disconnected => f.write_str("connection has been closed"),
+            ErrorCode::Lagged(lost) => f.write_fmt(format_args!("subscriber lagged behind and lost {lost} messages")),
+            ErrorCode::FrameTooLarge(len, max) => f.write_fmt(format_args!("packet of {len} bytes exceeds the maximum frame size of {max} bytes")),
+            ErrorCode::NameTooLong(ref name) => f.write_fmt(format_args!("name of {} bytes exceeds 255 bytes: {name}", name.len())),
+            ErrorCode::PacketTooLarge(len) => f.write_fmt(format_args!("packet of {len} bytes exceeds the maximum packet size")),
+            ErrorCode::UnknownPacketType(tag, _) => f.write_fmt(format_args!("unknown packet type {tag}")),
+            ErrorCode::TruncatedPacket(_) => f.write_str("packet has been truncated"),
+            ErrorCode::InvalidName(tag, _) => f.write_fmt(format_args!("name of packet type {tag} is not valid UTF-8")),
         }
     }
 }
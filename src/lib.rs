@@ -9,7 +9,7 @@
 //! ## Basic Usage
 //!
 //! 1. Refer to [Client-initiated commands][] and [Server-issued events][].
-//! 1. Define structs for the request and response.
+//! 1. Use the typed commands such as [`Client::version`], or define structs for the request and response of the others and call [`Client::request`].
 //! 1. Connect to the IKE daemon either over a Unix socket or a TCP connection.
 //!
 //! ## Hints on serializing/deserializing
@@ -27,22 +27,11 @@
 #![cfg_attr(not(unix), doc = "```ignore")]
 //! use std::error::Error;
 //!
-//! use serde::Deserialize;
-//!
-//! #[derive(Debug, Deserialize)]
-//! struct Version {
-//!     daemon: String,
-//!     version: String,
-//!     sysname: String,
-//!     release: String,
-//!     machine: String,
-//! }
-//!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn Error>> {
-//!     let mut client = rsvici::unix::connect("/run/charon.vici").await?;
+//!     let client = rsvici::unix::connect("/run/charon.vici").await?;
 //!
-//!     let version: Version = client.request("version", ()).await?;
+//!     let version = client.version().await?;
 //!     println!("Version: {:#?}", version);
 //!
 //!     Ok(())
@@ -55,7 +44,23 @@
 #[doc(inline)]
 pub use crate::client::*;
 #[doc(inline)]
+pub use crate::command::{Command, StreamCommand};
+#[doc(inline)]
 pub use crate::error::Error;
+#[doc(inline)]
+pub use crate::value::Value;
+
+/// Derives [`Command`], and [`StreamCommand`] if the event is given, for a request message. See [`rsvici::command`] for details.
+///
+/// [`rsvici::command`]: crate::command
+#[cfg(feature = "derive")]
+#[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
+pub use rsvici_derive::ViciCommand;
+
+#[macro_use]
+mod macros;
 
 mod client;
+pub mod command;
 pub mod error;
+pub mod value;
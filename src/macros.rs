@@ -0,0 +1,91 @@
+/// Builds a message as a [`Value`] from the syntax similar to the VICI protocol.
+///
+/// The elements of the message are written as `name: value` separated by commas, where `name` is either an identifier or a string literal. A value is
+/// either a section in braces, a list in brackets, or an expression that can be converted into a [`Value`], such as a string, an integer, or a
+/// `bool`.
+///
+/// # Example
+#[cfg_attr(unix, doc = "```no_run")]
+#[cfg_attr(not(unix), doc = "```ignore")]
+/// use std::error::Error;
+///
+/// use rsvici::{vici, Value};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn Error>> {
+///     let client = rsvici::unix::connect("/run/charon.vici").await?;
+///
+///     let child = "net-net";
+///     let message = vici! {
+///         ike: "gw-gw",
+///         child: child,
+///         timeout: 5000,
+///         "init-limits": false,
+///     };
+///
+///     let response: Value = client.request("initiate", message).await?;
+///     println!("Response: {:#?}", response);
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// [`Value`]: crate::Value
+#[macro_export]
+macro_rules! vici {
+    (@section $($tt:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut section = $crate::value::Section::new();
+        $crate::vici!(@entries section $($tt)*);
+        section
+    }};
+
+    (@entries $section:ident) => {};
+    (@entries $section:ident $key:ident : $($rest:tt)*) => {
+        $crate::vici!(@entry $section (stringify!($key)) $($rest)*);
+    };
+    (@entries $section:ident $key:literal : $($rest:tt)*) => {
+        $crate::vici!(@entry $section ($key) $($rest)*);
+    };
+
+    (@entry $section:ident ($key:expr) { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $section.insert(::std::string::ToString::to_string($key), $crate::vici!(@value { $($inner)* }));
+        $crate::vici!(@entries $section $($($rest)*)?);
+    };
+    (@entry $section:ident ($key:expr) [ $($inner:tt)* ] $(, $($rest:tt)*)?) => {
+        $section.insert(::std::string::ToString::to_string($key), $crate::vici!(@value [ $($inner)* ]));
+        $crate::vici!(@entries $section $($($rest)*)?);
+    };
+    (@entry $section:ident ($key:expr) $value:expr $(, $($rest:tt)*)?) => {
+        $section.insert(::std::string::ToString::to_string($key), $crate::Value::from($value));
+        $crate::vici!(@entries $section $($($rest)*)?);
+    };
+
+    (@items $list:ident) => {};
+    (@items $list:ident { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $list.push($crate::vici!(@value { $($inner)* }));
+        $crate::vici!(@items $list $($($rest)*)?);
+    };
+    (@items $list:ident [ $($inner:tt)* ] $(, $($rest:tt)*)?) => {
+        $list.push($crate::vici!(@value [ $($inner)* ]));
+        $crate::vici!(@items $list $($($rest)*)?);
+    };
+    (@items $list:ident $item:expr $(, $($rest:tt)*)?) => {
+        $list.push($crate::Value::from($item));
+        $crate::vici!(@items $list $($($rest)*)?);
+    };
+
+    (@value { $($inner:tt)* }) => {
+        $crate::Value::Section($crate::vici!(@section $($inner)*))
+    };
+    (@value [ $($inner:tt)* ]) => {{
+        #[allow(unused_mut)]
+        let mut list = ::std::vec::Vec::new();
+        $crate::vici!(@items list $($inner)*);
+        $crate::Value::List(list)
+    }};
+
+    ($($tt:tt)*) => {
+        $crate::Value::Section($crate::vici!(@section $($tt)*))
+    };
+}
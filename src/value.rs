@@ -0,0 +1,364 @@
+//! Messages of the VICI protocol without a predefined structure.
+
+use std::{
+    fmt::{self, Debug},
+    ops,
+};
+
+use indexmap::IndexMap;
+use serde::{
+    de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor},
+    ser::{Serialize, SerializeMap, SerializeSeq, Serializer},
+};
+
+/// An ordered section of a message, mapping the names of its elements to their values.
+pub type Section = IndexMap<String, Value>;
+
+/// An element of a message of the VICI protocol, which can be used in place of any type that implements `Serialize` or `Deserialize`.
+///
+/// Sections keep the order of their elements as they appear in the message.
+///
+/// # Example
+#[cfg_attr(unix, doc = "```no_run")]
+#[cfg_attr(not(unix), doc = "```ignore")]
+/// use std::error::Error;
+///
+/// use futures_util::{
+///     stream::TryStreamExt,
+///     pin_mut,
+/// };
+/// use rsvici::Value;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn Error>> {
+///     let client = rsvici::unix::connect("/run/charon.vici").await?;
+///
+///     let conns = client.stream_request::<(), Value>("list-conns", "list-conn", ());
+///     pin_mut!(conns);
+///
+///     while let Some(conn) = conns.try_next().await? {
+///         for (name, conn) in conn.as_section().unwrap() {
+///             println!("{}: {:?}", name, conn["local_addrs"][0]);
+///         }
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub enum Value {
+    /// A section containing named elements.
+    Section(Section),
+
+    /// A list of values.
+    List(Vec<Value>),
+
+    /// A value that is valid UTF-8.
+    String(String),
+
+    /// A value that is not valid UTF-8.
+    Bytes(Vec<u8>),
+}
+
+impl Value {
+    /// Returns the element of a section or list if it exists. A string indexes into a section, and a `usize` indexes into a list.
+    pub fn get<I: Index>(&self, index: I) -> Option<&Value> {
+        index.index_into(self)
+    }
+
+    /// Returns the mutable element of a section or list if it exists. A string indexes into a section, and a `usize` indexes into a list.
+    pub fn get_mut<I: Index>(&mut self, index: I) -> Option<&mut Value> {
+        index.index_into_mut(self)
+    }
+
+    /// Returns the elements if the value is a section.
+    pub fn as_section(&self) -> Option<&Section> {
+        match self {
+            Value::Section(section) => Some(section),
+            _ => None,
+        }
+    }
+
+    /// Returns the items if the value is a list.
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Returns the string if the value is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes of the value regardless of whether it is valid UTF-8.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::String(s) => Some(s.as_bytes()),
+            Value::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Section(section) => Debug::fmt(section, f),
+            Value::List(list) => Debug::fmt(list, f),
+            Value::String(s) => Debug::fmt(s, f),
+            Value::Bytes(b) => Debug::fmt(b, f),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<&String> for Value {
+    fn from(s: &String) -> Self {
+        Value::String(s.clone())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<bool> for Value {
+    /// Converts a `bool` into `"yes"` or `"no"`.
+    fn from(b: bool) -> Self {
+        Value::from(if b { "yes" } else { "no" })
+    }
+}
+
+macro_rules! impl_from_number {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(n: $ty) -> Self {
+                    Value::String(n.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl From<Section> for Value {
+    fn from(section: Section) -> Self {
+        Value::Section(section)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(list: Vec<T>) -> Self {
+        Value::List(list.into_iter().map(Into::into).collect())
+    }
+}
+
+/// A type that can index into a [`Value`]. Strings index into sections, and `usize` indexes into lists.
+pub trait Index: private::Sealed {
+    #[doc(hidden)]
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value>;
+
+    #[doc(hidden)]
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value>;
+}
+
+impl Index for usize {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        match v {
+            Value::List(list) => list.get(*self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        match v {
+            Value::List(list) => list.get_mut(*self),
+            _ => None,
+        }
+    }
+}
+
+impl Index for str {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        match v {
+            Value::Section(section) => section.get(self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        match v {
+            Value::Section(section) => section.get_mut(self),
+            _ => None,
+        }
+    }
+}
+
+impl Index for String {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        self[..].index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        self[..].index_into_mut(v)
+    }
+}
+
+impl<T> Index for &T
+where
+    T: ?Sized + Index,
+{
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        (**self).index_into_mut(v)
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for usize {}
+    impl Sealed for str {}
+    impl Sealed for String {}
+    impl<T> Sealed for &T where T: ?Sized + Sealed {}
+}
+
+impl<I: Index> ops::Index<I> for Value {
+    type Output = Value;
+
+    /// Returns the element of a section or list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element does not exist. Use [`Value::get`] to check it.
+    fn index(&self, index: I) -> &Value {
+        index.index_into(self).expect("no such element in the value")
+    }
+}
+
+impl<I: Index> ops::IndexMut<I> for Value {
+    /// Returns the mutable element of a section or list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element does not exist. Use [`Value::get_mut`] to check it.
+    fn index_mut(&mut self, index: I) -> &mut Value {
+        index.index_into_mut(self).expect("no such element in the value")
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Value::Section(section) => {
+                let mut map = serializer.serialize_map(Some(section.len()))?;
+                for (key, value) in section {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            },
+            Value::List(list) => {
+                let mut seq = serializer.serialize_seq(Some(list.len()))?;
+                for item in list {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            },
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Bytes(b) => serializer.serialize_bytes(b),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a section, list, or value")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_byte_buf(v.to_vec())
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        match String::from_utf8(v) {
+            Ok(s) => Ok(Value::String(s)),
+            Err(e) => Ok(Value::Bytes(e.into_bytes())),
+        }
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut list = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(item) = seq.next_element()? {
+            list.push(item);
+        }
+
+        Ok(Value::List(list))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut section = Section::with_capacity(map.size_hint().unwrap_or_default());
+        while let Some((key, value)) = map.next_entry()? {
+            section.insert(key, value);
+        }
+
+        Ok(Value::Section(section))
+    }
+}
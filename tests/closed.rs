@@ -0,0 +1,95 @@
+use std::io;
+
+use rsvici::{error::Category, Client};
+
+use futures_util::stream::TryStreamExt;
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use tokio_test::io::Builder;
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+struct Version {
+    daemon: String,
+    version: String,
+    sysname: String,
+    release: String,
+    machine: String,
+}
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+struct Log {
+    group: String,
+    level: u32,
+    msg: String,
+}
+
+#[tokio::test]
+async fn closed_while_requesting() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 9,
+            // packet type
+            0, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n',
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+    assert!(client.is_connected());
+
+    let actual = client.request::<(), Version>("version", ()).await.unwrap_err();
+    assert_eq!(actual.classify(), Category::Disconnected);
+
+    client.closed().await;
+    assert!(!client.is_connected());
+}
+
+#[tokio::test]
+async fn closed_while_subscribing() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let mut stream = Box::pin(client.subscribe::<Log>("log"));
+    let actual = stream.try_next().await.unwrap_err();
+    assert_eq!(actual.classify(), Category::Disconnected);
+
+    client.closed().await;
+    assert!(!client.is_connected());
+}
+
+#[tokio::test]
+async fn closed_while_writing() {
+    let (mock_stream, _handle) = Builder::new().write_error(io::ErrorKind::BrokenPipe.into()).build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    let actual = client.request::<(), Version>("version", ()).await.unwrap_err();
+    assert_eq!(actual.classify(), Category::Io);
+
+    client.closed().await;
+    assert!(!client.is_connected());
+
+    // Calls made after the connection has been closed fail just like the calls that were in progress.
+    let actual = client.request::<(), Version>("version", ()).await.unwrap_err();
+    assert_eq!(actual.classify(), Category::Disconnected);
+
+    let mut stream = Box::pin(client.subscribe::<Log>("log"));
+    let actual = stream.try_next().await.unwrap_err();
+    assert_eq!(actual.classify(), Category::Disconnected);
+}
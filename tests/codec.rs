@@ -0,0 +1,140 @@
+use rsvici::{
+    error::Category,
+    raw::{Packet, PacketType, ViciCodec},
+};
+
+use bytes::BytesMut;
+use futures_util::stream::TryStreamExt;
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use tokio_test::io::Builder;
+use tokio_util::codec::{Decoder, Encoder, FramedRead};
+
+#[test]
+fn encode() {
+    let mut codec = ViciCodec::new();
+    let mut dst = BytesMut::new();
+
+    #[rustfmt::skip]
+    let packet = Packet::new(PacketType::Event("log".into()), vec![
+        // msg = a
+        3, 3, b'm', b's', b'g', 0, 1, b'a',
+    ]);
+    codec.encode(&packet, &mut dst).unwrap();
+    codec.encode(Packet::new(PacketType::EventConfirm, vec![]), &mut dst).unwrap();
+
+    #[rustfmt::skip]
+    assert_eq!(
+        &dst[..],
+        &[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ][..],
+    );
+
+    let actual = codec
+        .encode(Packet::new(PacketType::CmdRequest("a".repeat(256).into()), vec![]), &mut dst)
+        .unwrap_err();
+    assert_eq!(actual.classify(), Category::Protocol);
+}
+
+#[test]
+fn decode_oversized() {
+    let mut codec = ViciCodec::with_max_frame_size(8);
+
+    #[rustfmt::skip]
+    let mut src = BytesMut::from(&[
+        // header
+        0, 0, 0, 13,
+        // packet type
+        7, 3, b'l', b'o', b'g',
+    ][..]);
+
+    let actual = codec.decode(&mut src).unwrap_err();
+    assert_eq!(actual.classify(), Category::Protocol);
+}
+
+#[test]
+fn decode_borrowed() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Log<'a> {
+        msg: &'a str,
+    }
+
+    let mut codec = ViciCodec::new();
+
+    #[rustfmt::skip]
+    let mut src = BytesMut::from(&[
+        // header
+        0, 0, 0, 13,
+        // packet type
+        7, 3, b'l', b'o', b'g',
+        // msg = a
+        3, 3, b'm', b's', b'g', 0, 1, b'a',
+    ][..]);
+    let range = src.as_ptr_range();
+
+    let packet = codec.decode(&mut src).unwrap().unwrap();
+    assert!(range.contains(&packet.payload().as_ptr()));
+
+    let PacketType::Event(name) = packet.packet_type() else {
+        panic!("unexpected packet type {}", packet.packet_type());
+    };
+    assert_eq!(name, "log");
+    assert!(range.contains(&name.as_ptr()));
+
+    let actual: Log = packet.message().unwrap();
+    assert_eq!(actual, Log { msg: "a" });
+    assert!(range.contains(&actual.msg.as_ptr()));
+}
+
+#[tokio::test]
+async fn decode_partial_reads() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .read(&[
+            // header
+            0, 0,
+        ])
+        .read(&[
+            // header
+            0, 13,
+            // packet type
+            7, 3, b'l',
+        ])
+        .read(&[
+            // packet type
+            b'o', b'g',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+            // header
+            0, 0, 0, 1,
+        ])
+        .read(&[
+            // packet type
+            5,
+        ])
+        .build();
+
+    let actual: Vec<_> = FramedRead::new(mock_stream, ViciCodec::new()).try_collect().await.unwrap();
+
+    #[rustfmt::skip]
+    assert_eq!(
+        actual,
+        vec![
+            Packet::new(PacketType::Event("log".into()), vec![
+                // msg = a
+                3, 3, b'm', b's', b'g', 0, 1, b'a',
+            ]),
+            Packet::new(PacketType::EventConfirm, vec![]),
+        ],
+    );
+}
@@ -0,0 +1,125 @@
+use rsvici::{vici, Client, Value, ViciCommand};
+
+use futures_util::stream::TryStreamExt;
+use pretty_assertions::assert_eq;
+use serde::{Deserialize, Serialize};
+use tokio_test::io::Builder;
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+struct Version {
+    daemon: String,
+    version: String,
+}
+
+#[derive(Serialize, ViciCommand)]
+#[vici(command = "version", response = Version)]
+struct GetVersion;
+
+#[derive(Serialize, ViciCommand)]
+#[vici(command = "list-conns", event = "list-conn", item = Value)]
+struct ListConns<'a> {
+    ike: &'a str,
+}
+
+#[tokio::test]
+async fn call() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 9,
+            // packet type
+            0, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 33,
+            // packet type
+            1,
+            // daemon = charon
+            3, 6, b'd', b'a', b'e', b'm', b'o', b'n', 0, 6, b'c', b'h', b'a', b'r', b'o', b'n',
+            // version = 5.9.5
+            3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 5, b'5', b'.', b'9', b'.', b'5',
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let actual = client.call(GetVersion).await.unwrap();
+    assert_eq!(
+        actual,
+        Version {
+            daemon: "charon".to_string(),
+            version: "5.9.5".to_string(),
+        },
+    );
+}
+
+#[tokio::test]
+async fn call_stream() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            3, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 25,
+            // packet type
+            0, 10, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n', b's',
+            // ike = conn-0
+            3, 3, b'i', b'k', b'e', 0, 6, b'c', b'o', b'n', b'n', b'-', b'0',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 36,
+            // packet type
+            7, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+            // conn-0
+            1, 6, b'c', b'o', b'n', b'n', b'-', b'0',
+            // version = IKEv2
+            3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 5, b'I', b'K', b'E', b'v', b'2',
+            // conn-0 end
+            2,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            4, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let actual: Vec<_> = client.call_stream(ListConns { ike: "conn-0" }).try_collect().await.unwrap();
+    assert_eq!(
+        actual,
+        vec![vici! {
+            "conn-0": {
+                version: "IKEv2",
+            },
+        }],
+    );
+}
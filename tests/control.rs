@@ -0,0 +1,419 @@
+use std::time::Duration;
+
+use rsvici::{
+    command::{
+        ControlLog, ControlOutcome, IkeSaSelector, InitiateOptions, InitiateOutcome, RedirectOptions, RekeyOptions, SaSelector, TerminateOptions,
+        TerminateOutcome, Timeout,
+    },
+    error::Category,
+    Client, StreamResponse,
+};
+
+use futures_util::stream::{StreamExt, TryStreamExt};
+use pretty_assertions::assert_eq;
+use tokio_test::io::Builder;
+
+#[tokio::test]
+async fn initiate() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            3, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 58,
+            // packet type
+            0, 8, b'i', b'n', b'i', b't', b'i', b'a', b't', b'e',
+            // child = net-net
+            3, 5, b'c', b'h', b'i', b'l', b'd', 0, 7, b'n', b'e', b't', b'-', b'n', b'e', b't',
+            // timeout = 1000
+            3, 7, b't', b'i', b'm', b'e', b'o', b'u', b't', 0, 4, b'1', b'0', b'0', b'0',
+            // init-limits = no
+            3, 11, b'i', b'n', b'i', b't', b'-', b'l', b'i', b'm', b'i', b't', b's', 0, 2, b'n', b'o',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 62,
+            // packet type
+            7, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+            // group = CFG
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'C', b'F', b'G',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = initiating 'net-net'
+            3, 3, b'm', b's', b'g', 0, 20, b'i', b'n', b'i', b't', b'i', b'a', b't', b'i', b'n', b'g', b' ', b'\'', b'n', b'e', b't', b'-', b'n', b'e', b't', b'\'',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 121,
+            // packet type
+            7, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // ikesa-name = gw-gw
+            3, 10, b'i', b'k', b'e', b's', b'a', b'-', b'n', b'a', b'm', b'e', 0, 5, b'g', b'w', b'-', b'g', b'w',
+            // ikesa-uniqueid = 12
+            3, 14, b'i', b'k', b'e', b's', b'a', b'-', b'u', b'n', b'i', b'q', b'u', b'e', b'i', b'd', 0, 2, b'1', b'2',
+            // msg = initiating IKE_SA gw-gw[12] to 192.0.2.1
+            3, 3, b'm', b's', b'g', 0, 40, b'i', b'n', b'i', b't', b'i', b'a', b't', b'i', b'n', b'g', b' ', b'I', b'K', b'E', b'_', b'S', b'A', b' ', b'g', b'w', b'-', b'g', b'w', b'[', b'1', b'2', b']', b' ', b't', b'o', b' ', b'1', b'9', b'2', b'.', b'0', b'.', b'2', b'.', b'1',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 15,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            4, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let options = InitiateOptions {
+        child: Some("net-net".to_string()),
+        timeout: Some(Timeout::After(Duration::from_secs(1))),
+        init_limits: Some(false),
+        ..Default::default()
+    };
+
+    let actual: Vec<_> = client.initiate(options).try_collect().await.unwrap();
+    assert_eq!(
+        actual,
+        vec![
+            StreamResponse::Event(ControlLog {
+                group: "CFG".to_string(),
+                level: 1,
+                ikesa_name: None,
+                ikesa_uniqueid: None,
+                msg: "initiating 'net-net'".to_string(),
+            }),
+            StreamResponse::Event(ControlLog {
+                group: "IKE".to_string(),
+                level: 1,
+                ikesa_name: Some("gw-gw".to_string()),
+                ikesa_uniqueid: Some(12),
+                msg: "initiating IKE_SA gw-gw[12] to 192.0.2.1".to_string(),
+            }),
+            StreamResponse::Response(InitiateOutcome { ikesa_uniqueid: Some(12) }),
+        ],
+    );
+}
+
+#[tokio::test]
+async fn initiate_failed() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            3, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 38,
+            // packet type
+            0, 8, b'i', b'n', b'i', b't', b'i', b'a', b't', b'e',
+            // child = net-net
+            3, 5, b'c', b'h', b'i', b'l', b'd', 0, 7, b'n', b'e', b't', b'-', b'n', b'e', b't',
+            // ike = gw-gw
+            3, 3, b'i', b'k', b'e', 0, 5, b'g', b'w', b'-', b'g', b'w',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 110,
+            // packet type
+            7, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // ikesa-name = gw-gw
+            3, 10, b'i', b'k', b'e', b's', b'a', b'-', b'n', b'a', b'm', b'e', 0, 5, b'g', b'w', b'-', b'g', b'w',
+            // ikesa-uniqueid = 13
+            3, 14, b'i', b'k', b'e', b's', b'a', b'-', b'u', b'n', b'i', b'q', b'u', b'e', b'i', b'd', 0, 2, b'1', b'3',
+            // msg = giving up after 5 retransmits
+            3, 3, b'm', b's', b'g', 0, 29, b'g', b'i', b'v', b'i', b'n', b'g', b' ', b'u', b'p', b' ', b'a', b'f', b't', b'e', b'r', b' ', b'5', b' ', b'r', b'e', b't', b'r', b'a', b'n', b's', b'm', b'i', b't', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 62,
+            // packet type
+            1,
+            // success = no
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 2, b'n', b'o',
+            // errmsg = establishing CHILD_SA 'net-net' failed
+            3, 6, b'e', b'r', b'r', b'm', b's', b'g', 0, 38, b'e', b's', b't', b'a', b'b', b'l', b'i', b's', b'h', b'i', b'n', b'g', b' ', b'C', b'H', b'I', b'L', b'D', b'_', b'S', b'A', b' ', b'\'', b'n', b'e', b't', b'-', b'n', b'e', b't', b'\'', b' ', b'f', b'a', b'i', b'l', b'e', b'd',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            4, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let options = InitiateOptions {
+        child: Some("net-net".to_string()),
+        ike: Some("gw-gw".to_string()),
+        ..Default::default()
+    };
+
+    let mut actual: Vec<_> = client.initiate(options).collect().await;
+    assert_eq!(actual.len(), 2);
+
+    let e = actual.pop().unwrap().unwrap_err();
+    assert_eq!(e.classify(), Category::CmdFailure);
+    assert_eq!(e.to_string(), "command failed: establishing CHILD_SA 'net-net' failed");
+
+    // The unique identifier of the IKE_SA is still available from the messages.
+    assert!(matches!(
+        actual.pop().unwrap().unwrap(),
+        StreamResponse::Event(ControlLog { ikesa_uniqueid: Some(13), .. }),
+    ));
+}
+
+#[tokio::test]
+async fn terminate() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            3, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 49,
+            // packet type
+            0, 9, b't', b'e', b'r', b'm', b'i', b'n', b'a', b't', b'e',
+            // ike-id = 12
+            3, 6, b'i', b'k', b'e', b'-', b'i', b'd', 0, 2, b'1', b'2',
+            // force = yes
+            3, 5, b'f', b'o', b'r', b'c', b'e', 0, 3, b'y', b'e', b's',
+            // timeout = 500
+            3, 7, b't', b'i', b'm', b'e', b'o', b'u', b't', 0, 3, b'5', b'0', b'0',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 106,
+            // packet type
+            7, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // ikesa-name = gw-gw
+            3, 10, b'i', b'k', b'e', b's', b'a', b'-', b'n', b'a', b'm', b'e', 0, 5, b'g', b'w', b'-', b'g', b'w',
+            // ikesa-uniqueid = 12
+            3, 14, b'i', b'k', b'e', b's', b'a', b'-', b'u', b'n', b'i', b'q', b'u', b'e', b'i', b'd', 0, 2, b'1', b'2',
+            // msg = deleting IKE_SA gw-gw[12]
+            3, 3, b'm', b's', b'g', 0, 25, b'd', b'e', b'l', b'e', b't', b'i', b'n', b'g', b' ', b'I', b'K', b'E', b'_', b'S', b'A', b' ', b'g', b'w', b'-', b'g', b'w', b'[', b'1', b'2', b']',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 42,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+            // matches = 1
+            3, 7, b'm', b'a', b't', b'c', b'h', b'e', b's', 0, 1, b'1',
+            // terminated = 1
+            3, 10, b't', b'e', b'r', b'm', b'i', b'n', b'a', b't', b'e', b'd', 0, 1, b'1',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            4, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let options = TerminateOptions {
+        selector: SaSelector {
+            ike_id: Some(12),
+            ..Default::default()
+        },
+        force: Some(true),
+        timeout: Some(Timeout::After(Duration::from_micros(499_001))),
+        ..Default::default()
+    };
+
+    let actual: Vec<_> = client.terminate(options).try_collect().await.unwrap();
+    assert_eq!(
+        actual,
+        vec![
+            StreamResponse::Event(ControlLog {
+                group: "IKE".to_string(),
+                level: 1,
+                ikesa_name: Some("gw-gw".to_string()),
+                ikesa_uniqueid: Some(12),
+                msg: "deleting IKE_SA gw-gw[12]".to_string(),
+            }),
+            StreamResponse::Response(TerminateOutcome { matches: 1, terminated: 1 }),
+        ],
+    );
+}
+
+#[tokio::test]
+async fn rekey_and_redirect() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 32,
+            // packet type
+            0, 5, b'r', b'e', b'k', b'e', b'y',
+            // ike = gw-gw
+            3, 3, b'i', b'k', b'e', 0, 5, b'g', b'w', b'-', b'g', b'w',
+            // reauth = yes
+            3, 6, b'r', b'e', b'a', b'u', b't', b'h', 0, 3, b'y', b'e', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 27,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+            // matches = 2
+            3, 7, b'm', b'a', b't', b'c', b'h', b'e', b's', 0, 1, b'2',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 64,
+            // packet type
+            0, 8, b'r', b'e', b'd', b'i', b'r', b'e', b'c', b't',
+            // ike-id = 3
+            3, 6, b'i', b'k', b'e', b'-', b'i', b'd', 0, 1, b'3',
+            // peer-ip = 192.0.2.0/24
+            3, 7, b'p', b'e', b'e', b'r', b'-', b'i', b'p', 0, 12, b'1', b'9', b'2', b'.', b'0', b'.', b'2', b'.', b'0', b'/', b'2', b'4',
+            // gateway = 192.0.2.2
+            3, 7, b'g', b'a', b't', b'e', b'w', b'a', b'y', 0, 9, b'1', b'9', b'2', b'.', b'0', b'.', b'2', b'.', b'2',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 69,
+            // packet type
+            1,
+            // success = no
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 2, b'n', b'o',
+            // errmsg = no matching SAs to redirect found
+            3, 6, b'e', b'r', b'r', b'm', b's', b'g', 0, 33, b'n', b'o', b' ', b'm', b'a', b't', b'c', b'h', b'i', b'n', b'g', b' ', b'S', b'A', b's', b' ', b't', b'o', b' ', b'r', b'e', b'd', b'i', b'r', b'e', b'c', b't', b' ', b'f', b'o', b'u', b'n', b'd',
+            // matches = 0
+            3, 7, b'm', b'a', b't', b'c', b'h', b'e', b's', 0, 1, b'0',
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let options = RekeyOptions {
+        selector: SaSelector {
+            ike: Some("gw-gw".to_string()),
+            ..Default::default()
+        },
+        reauth: Some(true),
+    };
+
+    let actual = client.rekey(options).await.unwrap();
+    assert_eq!(actual, ControlOutcome { matches: 2 });
+
+    let options = RedirectOptions {
+        selector: IkeSaSelector { ike: None, ike_id: Some(3) },
+        peer_ip: Some("192.0.2.0/24".to_string()),
+        ..RedirectOptions::new("192.0.2.2")
+    };
+
+    let actual = client.redirect(options).await.unwrap_err();
+    assert_eq!(actual.classify(), Category::CmdFailure);
+    assert_eq!(actual.to_string(), "command failed: no matching SAs to redirect found");
+}
+
+#[test]
+fn timeout() {
+    let serialize = |timeout| {
+        let options = TerminateOptions {
+            timeout: Some(timeout),
+            ..Default::default()
+        };
+        serde_vici::to_vec(&options).unwrap()
+    };
+
+    #[rustfmt::skip]
+    assert_eq!(serialize(Timeout::Immediate), [
+        // timeout = -1
+        3, 7, b't', b'i', b'm', b'e', b'o', b'u', b't', 0, 2, b'-', b'1',
+    ]);
+
+    #[rustfmt::skip]
+    assert_eq!(serialize(Timeout::After(Duration::ZERO)), [
+        // timeout = 1
+        3, 7, b't', b'i', b'm', b'e', b'o', b'u', b't', 0, 1, b'1',
+    ]);
+
+    #[rustfmt::skip]
+    assert_eq!(serialize(Timeout::After(Duration::from_micros(1))), [
+        // timeout = 1
+        3, 7, b't', b'i', b'm', b'e', b'o', b'u', b't', 0, 1, b'1',
+    ]);
+
+    #[rustfmt::skip]
+    assert_eq!(serialize(Timeout::After(Duration::MAX)), [
+        // timeout = 2147483647
+        3, 7, b't', b'i', b'm', b'e', b'o', b'u', b't', 0, 10, b'2', b'1', b'4', b'7', b'4', b'8', b'3', b'6', b'4', b'7',
+    ]);
+}
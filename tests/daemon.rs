@@ -0,0 +1,224 @@
+use rsvici::{
+    command::{IkeSaCount, Mallinfo, Priorities, Stats, Uptime, Version, Workers},
+    error::Category,
+    Client,
+};
+
+use pretty_assertions::assert_eq;
+use tokio_test::io::Builder;
+
+#[tokio::test]
+async fn version() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 9,
+            // packet type
+            0, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 82,
+            // packet type
+            1,
+            // daemon = charon
+            3, 6, b'd', b'a', b'e', b'm', b'o', b'n', 0, 6, b'c', b'h', b'a', b'r', b'o', b'n',
+            // version = 5.9.5
+            3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 5, b'5', b'.', b'9', b'.', b'5',
+            // sysname = Linux
+            3, 7, b's', b'y', b's', b'n', b'a', b'm', b'e', 0, 5, b'L', b'i', b'n', b'u', b'x',
+            // release = 6.1.0
+            3, 7, b'r', b'e', b'l', b'e', b'a', b's', b'e', 0, 5, b'6', b'.', b'1', b'.', b'0',
+            // machine = x86_64
+            3, 7, b'm', b'a', b'c', b'h', b'i', b'n', b'e', 0, 6, b'x', b'8', b'6', b'_', b'6', b'4',
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let actual = client.version().await.unwrap();
+    assert_eq!(
+        actual,
+        Version {
+            daemon: "charon".to_string(),
+            version: "5.9.5".to_string(),
+            sysname: "Linux".to_string(),
+            release: "6.1.0".to_string(),
+            machine: "x86_64".to_string(),
+        },
+    );
+}
+
+#[tokio::test]
+async fn stats() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 7,
+            // packet type
+            0, 5, b's', b't', b'a', b't', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 1, 71,
+            // packet type
+            1,
+            // uptime
+            1, 6, b'u', b'p', b't', b'i', b'm', b'e',
+            // running = 5 minutes
+            3, 7, b'r', b'u', b'n', b'n', b'i', b'n', b'g', 0, 9, b'5', b' ', b'm', b'i', b'n', b'u', b't', b'e', b's',
+            // since = Oct 16 12:00:00 2026
+            3, 5, b's', b'i', b'n', b'c', b'e', 0, 20, b'O', b'c', b't', b' ', b'1', b'6', b' ', b'1', b'2', b':', b'0', b'0', b':', b'0', b'0', b' ', b'2', b'0', b'2', b'6',
+            // uptime end
+            2,
+            // workers
+            1, 7, b'w', b'o', b'r', b'k', b'e', b'r', b's',
+            // total = 16
+            3, 5, b't', b'o', b't', b'a', b'l', 0, 2, b'1', b'6',
+            // idle = 11
+            3, 4, b'i', b'd', b'l', b'e', 0, 2, b'1', b'1',
+            // active
+            1, 6, b'a', b'c', b't', b'i', b'v', b'e',
+            // critical = 4
+            3, 8, b'c', b'r', b'i', b't', b'i', b'c', b'a', b'l', 0, 1, b'4',
+            // high = 0
+            3, 4, b'h', b'i', b'g', b'h', 0, 1, b'0',
+            // medium = 1
+            3, 6, b'm', b'e', b'd', b'i', b'u', b'm', 0, 1, b'1',
+            // low = 0
+            3, 3, b'l', b'o', b'w', 0, 1, b'0',
+            // active end
+            2,
+            // workers end
+            2,
+            // queues
+            1, 6, b'q', b'u', b'e', b'u', b'e', b's',
+            // critical = 0
+            3, 8, b'c', b'r', b'i', b't', b'i', b'c', b'a', b'l', 0, 1, b'0',
+            // high = 0
+            3, 4, b'h', b'i', b'g', b'h', 0, 1, b'0',
+            // medium = 0
+            3, 6, b'm', b'e', b'd', b'i', b'u', b'm', 0, 1, b'0',
+            // low = 0
+            3, 3, b'l', b'o', b'w', 0, 1, b'0',
+            // queues end
+            2,
+            // scheduled = 3
+            3, 9, b's', b'c', b'h', b'e', b'd', b'u', b'l', b'e', b'd', 0, 1, b'3',
+            // ikesas
+            1, 6, b'i', b'k', b'e', b's', b'a', b's',
+            // total = 2
+            3, 5, b't', b'o', b't', b'a', b'l', 0, 1, b'2',
+            // half-open = 1
+            3, 9, b'h', b'a', b'l', b'f', b'-', b'o', b'p', b'e', b'n', 0, 1, b'1',
+            // ikesas end
+            2,
+            // plugins
+            4, 7, b'p', b'l', b'u', b'g', b'i', b'n', b's',
+            // charon
+            5, 0, 6, b'c', b'h', b'a', b'r', b'o', b'n',
+            // vici
+            5, 0, 4, b'v', b'i', b'c', b'i',
+            // plugins end
+            6,
+            // mallinfo
+            1, 8, b'm', b'a', b'l', b'l', b'i', b'n', b'f', b'o',
+            // sbrk = 2711552
+            3, 4, b's', b'b', b'r', b'k', 0, 7, b'2', b'7', b'1', b'1', b'5', b'5', b'2',
+            // mmap = 0
+            3, 4, b'm', b'm', b'a', b'p', 0, 1, b'0',
+            // used = 580432
+            3, 4, b'u', b's', b'e', b'd', 0, 6, b'5', b'8', b'0', b'4', b'3', b'2',
+            // free = 2131120
+            3, 4, b'f', b'r', b'e', b'e', 0, 7, b'2', b'1', b'3', b'1', b'1', b'2', b'0',
+            // mallinfo end
+            2,
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let actual = client.stats().await.unwrap();
+    assert_eq!(
+        actual,
+        Stats {
+            uptime: Uptime {
+                running: "5 minutes".to_string(),
+                since: "Oct 16 12:00:00 2026".to_string(),
+            },
+            workers: Workers {
+                total: 16,
+                idle: 11,
+                active: Priorities {
+                    critical: 4,
+                    high: 0,
+                    medium: 1,
+                    low: 0,
+                },
+            },
+            queues: Priorities {
+                critical: 0,
+                high: 0,
+                medium: 0,
+                low: 0,
+            },
+            scheduled: 3,
+            ikesas: IkeSaCount { total: 2, half_open: 1 },
+            plugins: vec!["charon".to_string(), "vici".to_string()],
+            mem: None,
+            mallinfo: Some(Mallinfo {
+                sbrk: 2711552,
+                mmap: 0,
+                used: 580432,
+                free: 2131120,
+            }),
+        },
+    );
+}
+
+#[tokio::test]
+async fn reload_settings() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 15,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 49,
+            // packet type
+            1,
+            // success = no
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 2, b'n', b'o',
+            // errmsg = reloading settings failed
+            3, 6, b'e', b'r', b'r', b'm', b's', b'g', 0, 25, b'r', b'e', b'l', b'o', b'a', b'd', b'i', b'n', b'g', b' ', b's', b'e', b't', b't', b'i', b'n', b'g', b's', b' ', b'f', b'a', b'i', b'l', b'e', b'd',
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    client.reload_settings().await.unwrap();
+
+    let actual = client.reload_settings().await.unwrap_err();
+    assert_eq!(actual.classify(), Category::CmdFailure);
+    assert_eq!(actual.to_string(), "command failed: reloading settings failed");
+}
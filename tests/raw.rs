@@ -0,0 +1,127 @@
+use rsvici::{
+    error::Category,
+    raw::{self, Packet, PacketType},
+    Client,
+};
+
+use futures_util::stream::{StreamExt, TryStreamExt};
+use pretty_assertions::assert_eq;
+use tokio_test::io::Builder;
+
+#[test]
+fn encode_decode() {
+    let packet = Packet::new(
+        PacketType::Event("log".into()),
+        vec![
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+        ],
+    );
+
+    let actual = raw::encode(&packet).unwrap();
+    #[rustfmt::skip]
+    assert_eq!(
+        actual,
+        vec![
+            // header
+            0, 0, 0, 13,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+        ],
+    );
+
+    assert_eq!(raw::decode(&actual[..10]).unwrap(), None);
+    assert_eq!(raw::decode(&actual).unwrap(), Some((packet, 17)));
+
+    let actual = raw::decode(&[0, 0, 0, 2, 9, 0]).unwrap_err();
+    assert_eq!(actual.classify(), Category::Protocol);
+    assert_eq!(actual.raw_packet(), Some(&[9, 0][..]));
+}
+
+#[tokio::test]
+async fn raw_request() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 22,
+            // packet type
+            0, 8, b'i', b'n', b'i', b't', b'i', b'a', b't', b'e',
+            // child = net
+            3, 5, b'c', b'h', b'i', b'l', b'd', 0, 3, b'n', b'e', b't',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 15,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    #[rustfmt::skip]
+    let actual = client
+        .raw_request("initiate", vec![
+            // child = net
+            3, 5, b'c', b'h', b'i', b'l', b'd', 0, 3, b'n', b'e', b't',
+        ])
+        .await
+        .unwrap();
+
+    #[rustfmt::skip]
+    assert_eq!(
+        actual,
+        vec![
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ],
+    );
+}
+
+#[tokio::test]
+async fn raw_subscribe() {
+    #[rustfmt::skip]
+    let (mock_stream, _handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    let stream = client.raw_subscribe("log");
+    let actual: Vec<_> = stream.take(1).try_collect().await.unwrap();
+
+    #[rustfmt::skip]
+    assert_eq!(
+        actual,
+        vec![
+            vec![
+                // msg = a
+                3, 3, b'm', b's', b'g', 0, 1, b'a',
+            ],
+        ],
+    );
+}
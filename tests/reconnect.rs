@@ -0,0 +1,187 @@
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use rsvici::{Client, ConnectionState, ReconnectingClient};
+
+use futures_util::{stream::TryStreamExt, StreamExt};
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use tokio_test::io::{Builder, Mock};
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+struct Log {
+    group: String,
+    level: u32,
+    msg: String,
+}
+
+#[tokio::test]
+async fn reconnect_subscribe() {
+    #[rustfmt::skip]
+    let first = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 66,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = received FRAGMENTATION vendor ID
+            3, 3, b'm', b's', b'g', 0, 32, b'r', b'e', b'c', b'e', b'i', b'v', b'e', b'd', b' ', b'F', b'R', b'A', b'G', b'M', b'E', b'N', b'T', b'A', b'T', b'I', b'O', b'N', b' ', b'v', b'e', b'n', b'd', b'o', b'r', b' ', b'I', b'D',
+        ])
+        .build();
+
+    #[rustfmt::skip]
+    let (second, _handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 56,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = received DPD vendor ID
+            3, 3, b'm', b's', b'g', 0, 22, b'r', b'e', b'c', b'e', b'i', b'v', b'e', b'd', b' ', b'D', b'P', b'D', b' ', b'v', b'e', b'n', b'd', b'o', b'r', b' ', b'I', b'D',
+        ])
+        .build_with_handle();
+
+    let sessions = Arc::new(Mutex::new(VecDeque::from([first, second])));
+    let client = ReconnectingClient::new(move || connect(sessions.clone()));
+    let states = client.connection_states();
+
+    let stream = client.subscribe::<Log>("log");
+    let actual: Vec<_> = stream.take(2).try_collect().await.unwrap();
+    assert_eq!(
+        actual,
+        vec![
+            Log {
+                group: "IKE".to_string(),
+                level: 1,
+                msg: "received FRAGMENTATION vendor ID".to_string(),
+            },
+            Log {
+                group: "IKE".to_string(),
+                level: 1,
+                msg: "received DPD vendor ID".to_string(),
+            },
+        ]
+    );
+
+    let actual: Vec<_> = states.take(4).collect().await;
+    assert_eq!(
+        actual,
+        vec![
+            ConnectionState::Connected,
+            ConnectionState::Disconnected,
+            ConnectionState::Connected,
+            ConnectionState::Resubscribed("log".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reconnect_connect_failed() {
+    let sessions = Arc::new(Mutex::new(VecDeque::new()));
+    let client = ReconnectingClient::new(move || connect(sessions.clone()));
+    let states = client.connection_states();
+
+    let actual: Vec<_> = states.take(1).collect().await;
+    assert_eq!(
+        actual,
+        vec![ConnectionState::ConnectFailed {
+            kind: io::ErrorKind::ConnectionRefused,
+            message: io::Error::from(io::ErrorKind::ConnectionRefused).to_string(),
+        }]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn reconnect_subscribe_timeout() {
+    #[rustfmt::skip]
+    let (session, _handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .wait(Duration::from_secs(2))
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 56,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = received DPD vendor ID
+            3, 3, b'm', b's', b'g', 0, 22, b'r', b'e', b'c', b'e', b'i', b'v', b'e', b'd', b' ', b'D', b'P', b'D', b' ', b'v', b'e', b'n', b'd', b'o', b'r', b' ', b'I', b'D',
+        ])
+        .build_with_handle();
+
+    let sessions = Arc::new(Mutex::new(VecDeque::from([session])));
+    let client = ReconnectingClient::new(move || {
+        let sessions = sessions.clone();
+        async move {
+            let mut client = connect(sessions).await?;
+            client.set_timeout(Some(Duration::from_secs(1)));
+            Ok(client)
+        }
+    });
+
+    // The registration is retried after it has timed out, instead of ending the subscription.
+    let stream = client.subscribe::<Log>("log");
+    let actual: Vec<_> = stream.take(1).try_collect().await.unwrap();
+    assert_eq!(
+        actual,
+        vec![Log {
+            group: "IKE".to_string(),
+            level: 1,
+            msg: "received DPD vendor ID".to_string(),
+        }]
+    );
+}
+
+async fn connect(sessions: Arc<Mutex<VecDeque<Mock>>>) -> io::Result<Client> {
+    let session = sessions.lock().unwrap().pop_front().ok_or(io::ErrorKind::ConnectionRefused)?;
+    Ok(Client::new(session))
+}
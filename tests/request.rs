@@ -1,7 +1,23 @@
-use rsvici::{error::Category, Client};
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
 
+use rsvici::{error::Category, Client, ClientOptions, Value};
+
+use futures_util::{future, poll, StreamExt};
 use pretty_assertions::assert_eq;
 use serde::Deserialize;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    task,
+};
 use tokio_test::io::Builder;
 
 #[derive(Debug, Deserialize, Eq, PartialEq)]
@@ -13,6 +29,11 @@ struct Version {
     machine: String,
 }
 
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+struct ReloadSettings {
+    success: bool,
+}
+
 #[tokio::test]
 async fn request() {
     #[rustfmt::skip]
@@ -41,7 +62,7 @@ async fn request() {
         ])
         .build();
 
-    let mut client = Client::new(mock_stream);
+    let client = Client::new(mock_stream);
 
     let actual: Version = client.request("version", ()).await.unwrap();
     assert_eq!(
@@ -56,6 +77,79 @@ async fn request() {
     );
 }
 
+/// Counts the calls to write to the underlying stream.
+struct CountWrites<S> {
+    inner: S,
+    writes: Arc<AtomicUsize>,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountWrites<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountWrites<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[tokio::test]
+async fn request_single_write() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 9,
+            // packet type
+            0, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 15,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ])
+        .build();
+
+    let writes = Arc::new(AtomicUsize::new(0));
+    let client = Client::new(CountWrites {
+        inner: mock_stream,
+        writes: writes.clone(),
+    });
+
+    client.request::<_, ()>("version", ()).await.unwrap();
+    assert_eq!(writes.load(Ordering::SeqCst), 1);
+
+    let actual: ReloadSettings = client.request("reload-settings", ()).await.unwrap();
+    assert_eq!(actual, ReloadSettings { success: true });
+    assert_eq!(writes.load(Ordering::SeqCst), 2);
+}
+
 #[tokio::test]
 async fn request_unknown_cmd() {
     #[rustfmt::skip]
@@ -74,8 +168,298 @@ async fn request_unknown_cmd() {
         ])
         .build();
 
-    let mut client = Client::new(mock_stream);
+    let client = Client::new(mock_stream);
 
     let actual = client.request::<(), Version>("non-existing", ()).await.unwrap_err();
     assert_eq!(actual.classify(), Category::UnknownCmd);
 }
+
+#[tokio::test]
+async fn request_concurrently() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 9,
+            // packet type
+            0, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 100,
+            // packet type
+            1,
+            // daemon = charon-systemd
+            3, 6, b'd', b'a', b'e', b'm', b'o', b'n', 0, 14, b'c', b'h', b'a', b'r', b'o', b'n', b'-', b's', b'y', b's', b't', b'e', b'm', b'd',
+            // version = 5.9.5
+            3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 5, b'5', b'.', b'9', b'.', b'5',
+            // sysname = Linux
+            3, 7, b's', b'y', b's', b'n', b'a', b'm', b'e', 0, 5, b'L', b'i', b'n', b'u', b'x',
+            // release = 5.16.16-arch1-1
+            3, 7, b'r', b'e', b'l', b'e', b'a', b's', b'e', 0, 15, b'5', b'.', b'1', b'6', b'.', b'1', b'6', b'-', b'a', b'r', b'c', b'h', b'1', b'-', b'1',
+            // machine = x86_64
+            3, 7, b'm', b'a', b'c', b'h', b'i', b'n', b'e', 0, 6, b'x', b'8', b'6', b'_', b'6', b'4',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 15,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+    let cloned = client.clone();
+
+    let (version, reload) = tokio::join!(
+        client.request::<(), Version>("version", ()),
+        cloned.request::<(), ReloadSettings>("reload-settings", ()),
+    );
+    assert_eq!(
+        version.unwrap(),
+        Version {
+            daemon: "charon-systemd".to_string(),
+            version: "5.9.5".to_string(),
+            sysname: "Linux".to_string(),
+            release: "5.16.16-arch1-1".to_string(),
+            machine: "x86_64".to_string(),
+        }
+    );
+    assert_eq!(reload.unwrap(), ReloadSettings { success: true });
+}
+
+#[tokio::test(start_paused = true)]
+async fn request_timeout() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 9,
+            // packet type
+            0, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n',
+        ])
+        .wait(Duration::from_secs(10))
+        .read(&[
+            // header
+            0, 0, 0, 100,
+            // packet type
+            1,
+            // daemon = charon-systemd
+            3, 6, b'd', b'a', b'e', b'm', b'o', b'n', 0, 14, b'c', b'h', b'a', b'r', b'o', b'n', b'-', b's', b'y', b's', b't', b'e', b'm', b'd',
+            // version = 5.9.5
+            3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 5, b'5', b'.', b'9', b'.', b'5',
+            // sysname = Linux
+            3, 7, b's', b'y', b's', b'n', b'a', b'm', b'e', 0, 5, b'L', b'i', b'n', b'u', b'x',
+            // release = 5.16.16-arch1-1
+            3, 7, b'r', b'e', b'l', b'e', b'a', b's', b'e', 0, 15, b'5', b'.', b'1', b'6', b'.', b'1', b'6', b'-', b'a', b'r', b'c', b'h', b'1', b'-', b'1',
+            // machine = x86_64
+            3, 7, b'm', b'a', b'c', b'h', b'i', b'n', b'e', 0, 6, b'x', b'8', b'6', b'_', b'6', b'4',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 15,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ])
+        .build();
+
+    let mut client = Client::new(mock_stream);
+    client.set_timeout(Some(Duration::from_secs(30)));
+
+    let actual = client
+        .request_with_timeout::<(), Version>("version", (), Duration::from_secs(1))
+        .await
+        .unwrap_err();
+    assert_eq!(actual.classify(), Category::Timeout);
+
+    let actual: ReloadSettings = client.request("reload-settings", ()).await.unwrap();
+    assert_eq!(actual, ReloadSettings { success: true });
+}
+
+#[tokio::test(start_paused = true)]
+async fn request_timeout_while_held_back() {
+    #[rustfmt::skip]
+    let (mock_stream, _handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = b
+            3, 3, b'm', b's', b'g', 0, 1, b'b',
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    // The subscriber does not consume its messages, which holds back the listener once its buffer is full.
+    let mut stream = Box::pin(client.subscribe::<Value>("log"));
+    assert!(poll!(stream.next()).is_pending());
+    for _ in 0..4 {
+        task::yield_now().await;
+    }
+
+    // More requests than the listener can queue wait for it within their timeout.
+    let requests = (0..16).map(|_| client.request_with_timeout::<(), ()>("version", (), Duration::from_secs(1)));
+    for actual in future::join_all(requests).await {
+        assert_eq!(actual.unwrap_err().classify(), Category::Timeout);
+    }
+}
+
+#[tokio::test]
+async fn request_frame_too_large() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 9,
+            // packet type
+            0, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 100,
+            // packet type
+            1,
+            // daemon = charon-systemd
+            3, 6, b'd', b'a', b'e', b'm', b'o', b'n', 0, 14, b'c', b'h', b'a', b'r', b'o', b'n', b'-', b's', b'y', b's', b't', b'e', b'm', b'd',
+            // version = 5.9.5
+            3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 5, b'5', b'.', b'9', b'.', b'5',
+            // sysname = Linux
+            3, 7, b's', b'y', b's', b'n', b'a', b'm', b'e', 0, 5, b'L', b'i', b'n', b'u', b'x',
+            // release = 5.16.16-arch1-1
+            3, 7, b'r', b'e', b'l', b'e', b'a', b's', b'e', 0, 15, b'5', b'.', b'1', b'6', b'.', b'1', b'6', b'-', b'a', b'r', b'c', b'h', b'1', b'-', b'1',
+            // machine = x86_64
+            3, 7, b'm', b'a', b'c', b'h', b'i', b'n', b'e', 0, 6, b'x', b'8', b'6', b'_', b'6', b'4',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 15,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ])
+        .build();
+
+    let client = Client::with_options(
+        mock_stream,
+        ClientOptions {
+            max_frame_size: 64,
+            ..Default::default()
+        },
+    );
+
+    let actual = client.request::<(), Version>("version", ()).await.unwrap_err();
+    assert_eq!(actual.classify(), Category::Protocol);
+
+    let actual: ReloadSettings = client.request("reload-settings", ()).await.unwrap();
+    assert_eq!(actual, ReloadSettings { success: true });
+}
+
+#[tokio::test]
+async fn request_name_too_long() {
+    let (mock_stream, _handle) = Builder::new().build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    let actual = client.request::<(), ()>(&"a".repeat(256), ()).await.unwrap_err();
+    assert_eq!(actual.classify(), Category::Protocol);
+}
+
+#[tokio::test]
+async fn request_invalid_packets() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 3,
+            // packet type
+            9, 1, 2,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 4,
+            // packet type
+            7, 2, 0xff, 0xfe,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 15,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ])
+        .build();
+
+    let client = Client::with_options(
+        mock_stream,
+        ClientOptions {
+            strict: true,
+            ..Default::default()
+        },
+    );
+
+    let mut errors = Box::pin(client.listen_for_errors());
+    task::yield_now().await;
+
+    let actual: ReloadSettings = client.request("reload-settings", ()).await.unwrap();
+    assert_eq!(actual, ReloadSettings { success: true });
+
+    let actual = errors.next().await.unwrap();
+    assert_eq!(actual.classify(), Category::Protocol);
+    assert_eq!(actual.raw_packet(), Some(&[9, 1, 2][..]));
+
+    let actual = errors.next().await.unwrap();
+    assert_eq!(actual.classify(), Category::Protocol);
+    assert_eq!(actual.raw_packet(), Some(&[7, 2, 0xff, 0xfe][..]));
+}
@@ -0,0 +1,328 @@
+use indexmap::indexmap;
+
+use rsvici::{
+    command::{ChildSa, ChildSaState, IkeSa, IkeSaState, IpsecMode, IpsecProtocol, ListSasOptions},
+    Client,
+};
+
+use futures_util::stream::TryStreamExt;
+use pretty_assertions::assert_eq;
+use tokio_test::io::Builder;
+
+#[tokio::test]
+async fn list_sas() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 9,
+            // packet type
+            3, 7, b'l', b'i', b's', b't', b'-', b's', b'a',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 36,
+            // packet type
+            0, 8, b'l', b'i', b's', b't', b'-', b's', b'a', b's',
+            // noblock = yes
+            3, 7, b'n', b'o', b'b', b'l', b'o', b'c', b'k', 0, 3, b'y', b'e', b's',
+            // ike = gw-gw
+            3, 3, b'i', b'k', b'e', 0, 5, b'g', b'w', b'-', b'g', b'w',
+        ])
+        .read(&[
+            // header
+            0, 0, 3, 104,
+            // packet type
+            7, 7, b'l', b'i', b's', b't', b'-', b's', b'a',
+            // gw-gw
+            1, 5, b'g', b'w', b'-', b'g', b'w',
+            // uniqueid = 12
+            3, 8, b'u', b'n', b'i', b'q', b'u', b'e', b'i', b'd', 0, 2, b'1', b'2',
+            // version = 2
+            3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 1, b'2',
+            // state = ESTABLISHED
+            3, 5, b's', b't', b'a', b't', b'e', 0, 11, b'E', b'S', b'T', b'A', b'B', b'L', b'I', b'S', b'H', b'E', b'D',
+            // local-host = 192.0.2.1
+            3, 10, b'l', b'o', b'c', b'a', b'l', b'-', b'h', b'o', b's', b't', 0, 9, b'1', b'9', b'2', b'.', b'0', b'.', b'2', b'.', b'1',
+            // local-port = 4500
+            3, 10, b'l', b'o', b'c', b'a', b'l', b'-', b'p', b'o', b'r', b't', 0, 4, b'4', b'5', b'0', b'0',
+            // local-id = gw1
+            3, 8, b'l', b'o', b'c', b'a', b'l', b'-', b'i', b'd', 0, 3, b'g', b'w', b'1',
+            // remote-host = 192.0.2.2
+            3, 11, b'r', b'e', b'm', b'o', b't', b'e', b'-', b'h', b'o', b's', b't', 0, 9, b'1', b'9', b'2', b'.', b'0', b'.', b'2', b'.', b'2',
+            // remote-port = 4500
+            3, 11, b'r', b'e', b'm', b'o', b't', b'e', b'-', b'p', b'o', b'r', b't', 0, 4, b'4', b'5', b'0', b'0',
+            // remote-id = gw2
+            3, 9, b'r', b'e', b'm', b'o', b't', b'e', b'-', b'i', b'd', 0, 3, b'g', b'w', b'2',
+            // initiator = yes
+            3, 9, b'i', b'n', b'i', b't', b'i', b'a', b't', b'o', b'r', 0, 3, b'y', b'e', b's',
+            // initiator-spi = 1f3c5a7e9b2d4f60
+            3, 13, b'i', b'n', b'i', b't', b'i', b'a', b't', b'o', b'r', b'-', b's', b'p', b'i', 0, 16, b'1', b'f', b'3', b'c', b'5', b'a', b'7', b'e', b'9', b'b', b'2', b'd', b'4', b'f', b'6', b'0',
+            // responder-spi = 8a6c4e2f0b1d3957
+            3, 13, b'r', b'e', b's', b'p', b'o', b'n', b'd', b'e', b'r', b'-', b's', b'p', b'i', 0, 16, b'8', b'a', b'6', b'c', b'4', b'e', b'2', b'f', b'0', b'b', b'1', b'd', b'3', b'9', b'5', b'7',
+            // nat-remote = yes
+            3, 10, b'n', b'a', b't', b'-', b'r', b'e', b'm', b'o', b't', b'e', 0, 3, b'y', b'e', b's',
+            // nat-any = yes
+            3, 7, b'n', b'a', b't', b'-', b'a', b'n', b'y', 0, 3, b'y', b'e', b's',
+            // encr-alg = AES_CBC
+            3, 8, b'e', b'n', b'c', b'r', b'-', b'a', b'l', b'g', 0, 7, b'A', b'E', b'S', b'_', b'C', b'B', b'C',
+            // encr-keysize = 256
+            3, 12, b'e', b'n', b'c', b'r', b'-', b'k', b'e', b'y', b's', b'i', b'z', b'e', 0, 3, b'2', b'5', b'6',
+            // integ-alg = HMAC_SHA2_256_128
+            3, 9, b'i', b'n', b't', b'e', b'g', b'-', b'a', b'l', b'g', 0, 17, b'H', b'M', b'A', b'C', b'_', b'S', b'H', b'A', b'2', b'_', b'2', b'5', b'6', b'_', b'1', b'2', b'8',
+            // prf-alg = PRF_HMAC_SHA2_256
+            3, 7, b'p', b'r', b'f', b'-', b'a', b'l', b'g', 0, 17, b'P', b'R', b'F', b'_', b'H', b'M', b'A', b'C', b'_', b'S', b'H', b'A', b'2', b'_', b'2', b'5', b'6',
+            // dh-group = CURVE_25519
+            3, 8, b'd', b'h', b'-', b'g', b'r', b'o', b'u', b'p', 0, 11, b'C', b'U', b'R', b'V', b'E', b'_', b'2', b'5', b'5', b'1', b'9',
+            // established = 120
+            3, 11, b'e', b's', b't', b'a', b'b', b'l', b'i', b's', b'h', b'e', b'd', 0, 3, b'1', b'2', b'0',
+            // rekey-time = 13800
+            3, 10, b'r', b'e', b'k', b'e', b'y', b'-', b't', b'i', b'm', b'e', 0, 5, b'1', b'3', b'8', b'0', b'0',
+            // tasks-queued
+            4, 12, b't', b'a', b's', b'k', b's', b'-', b'q', b'u', b'e', b'u', b'e', b'd',
+            // IKE_DPD
+            5, 0, 7, b'I', b'K', b'E', b'_', b'D', b'P', b'D',
+            // tasks-queued end
+            6,
+            // child-sas
+            1, 9, b'c', b'h', b'i', b'l', b'd', b'-', b's', b'a', b's',
+            // net-net-3
+            1, 9, b'n', b'e', b't', b'-', b'n', b'e', b't', b'-', b'3',
+            // name = net-net
+            3, 4, b'n', b'a', b'm', b'e', 0, 7, b'n', b'e', b't', b'-', b'n', b'e', b't',
+            // uniqueid = 3
+            3, 8, b'u', b'n', b'i', b'q', b'u', b'e', b'i', b'd', 0, 1, b'3',
+            // reqid = 1
+            3, 5, b'r', b'e', b'q', b'i', b'd', 0, 1, b'1',
+            // state = INSTALLED
+            3, 5, b's', b't', b'a', b't', b'e', 0, 9, b'I', b'N', b'S', b'T', b'A', b'L', b'L', b'E', b'D',
+            // mode = TUNNEL
+            3, 4, b'm', b'o', b'd', b'e', 0, 6, b'T', b'U', b'N', b'N', b'E', b'L',
+            // protocol = ESP
+            3, 8, b'p', b'r', b'o', b't', b'o', b'c', b'o', b'l', 0, 3, b'E', b'S', b'P',
+            // encap = yes
+            3, 5, b'e', b'n', b'c', b'a', b'p', 0, 3, b'y', b'e', b's',
+            // spi-in = c1a2b3d4
+            3, 6, b's', b'p', b'i', b'-', b'i', b'n', 0, 8, b'c', b'1', b'a', b'2', b'b', b'3', b'd', b'4',
+            // spi-out = c5d6e7f8
+            3, 7, b's', b'p', b'i', b'-', b'o', b'u', b't', 0, 8, b'c', b'5', b'd', b'6', b'e', b'7', b'f', b'8',
+            // encr-alg = AES_GCM_16
+            3, 8, b'e', b'n', b'c', b'r', b'-', b'a', b'l', b'g', 0, 10, b'A', b'E', b'S', b'_', b'G', b'C', b'M', b'_', b'1', b'6',
+            // encr-keysize = 256
+            3, 12, b'e', b'n', b'c', b'r', b'-', b'k', b'e', b'y', b's', b'i', b'z', b'e', 0, 3, b'2', b'5', b'6',
+            // esn = 1
+            3, 3, b'e', b's', b'n', 0, 1, b'1',
+            // bytes-in = 1024
+            3, 8, b'b', b'y', b't', b'e', b's', b'-', b'i', b'n', 0, 4, b'1', b'0', b'2', b'4',
+            // packets-in = 8
+            3, 10, b'p', b'a', b'c', b'k', b'e', b't', b's', b'-', b'i', b'n', 0, 1, b'8',
+            // use-in = 5
+            3, 6, b'u', b's', b'e', b'-', b'i', b'n', 0, 1, b'5',
+            // bytes-out = 2048
+            3, 9, b'b', b'y', b't', b'e', b's', b'-', b'o', b'u', b't', 0, 4, b'2', b'0', b'4', b'8',
+            // packets-out = 16
+            3, 11, b'p', b'a', b'c', b'k', b'e', b't', b's', b'-', b'o', b'u', b't', 0, 2, b'1', b'6',
+            // use-out = 4
+            3, 7, b'u', b's', b'e', b'-', b'o', b'u', b't', 0, 1, b'4',
+            // rekey-time = 3300
+            3, 10, b'r', b'e', b'k', b'e', b'y', b'-', b't', b'i', b'm', b'e', 0, 4, b'3', b'3', b'0', b'0',
+            // life-time = 3900
+            3, 9, b'l', b'i', b'f', b'e', b'-', b't', b'i', b'm', b'e', 0, 4, b'3', b'9', b'0', b'0',
+            // install-time = 120
+            3, 12, b'i', b'n', b's', b't', b'a', b'l', b'l', b'-', b't', b'i', b'm', b'e', 0, 3, b'1', b'2', b'0',
+            // local-ts
+            4, 8, b'l', b'o', b'c', b'a', b'l', b'-', b't', b's',
+            // 10.1.0.0/16
+            5, 0, 11, b'1', b'0', b'.', b'1', b'.', b'0', b'.', b'0', b'/', b'1', b'6',
+            // local-ts end
+            6,
+            // remote-ts
+            4, 9, b'r', b'e', b'm', b'o', b't', b'e', b'-', b't', b's',
+            // 10.2.0.0/16
+            5, 0, 11, b'1', b'0', b'.', b'2', b'.', b'0', b'.', b'0', b'/', b'1', b'6',
+            // remote-ts end
+            6,
+            // net-net-3 end
+            2,
+            // child-sas end
+            2,
+            // gw-gw end
+            2,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 128,
+            // packet type
+            7, 7, b'l', b'i', b's', b't', b'-', b's', b'a',
+            // gw-gw
+            1, 5, b'g', b'w', b'-', b'g', b'w',
+            // uniqueid = 13
+            3, 8, b'u', b'n', b'i', b'q', b'u', b'e', b'i', b'd', 0, 2, b'1', b'3',
+            // version = 2
+            3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 1, b'2',
+            // state = CONNECTING
+            3, 5, b's', b't', b'a', b't', b'e', 0, 10, b'C', b'O', b'N', b'N', b'E', b'C', b'T', b'I', b'N', b'G',
+            // initiator-spi = 2b4d6f8091a3c5e7
+            3, 13, b'i', b'n', b'i', b't', b'i', b'a', b't', b'o', b'r', b'-', b's', b'p', b'i', 0, 16, b'2', b'b', b'4', b'd', b'6', b'f', b'8', b'0', b'9', b'1', b'a', b'3', b'c', b'5', b'e', b'7',
+            // responder-spi = 0000000000000000
+            3, 13, b'r', b'e', b's', b'p', b'o', b'n', b'd', b'e', b'r', b'-', b's', b'p', b'i', 0, 16, b'0', b'0', b'0', b'0', b'0', b'0', b'0', b'0', b'0', b'0', b'0', b'0', b'0', b'0', b'0', b'0',
+            // gw-gw end
+            2,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 9,
+            // packet type
+            4, 7, b'l', b'i', b's', b't', b'-', b's', b'a',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let options = ListSasOptions {
+        noblock: Some(true),
+        ike: Some("gw-gw".to_string()),
+        ike_id: None,
+    };
+
+    let actual: Vec<_> = client.list_sas(options).try_collect().await.unwrap();
+    assert_eq!(
+        actual,
+        vec![
+            IkeSa {
+                name: "gw-gw".to_string(),
+                uniqueid: 12,
+                version: 2,
+                state: IkeSaState::Established,
+                local_host: Some("192.0.2.1".to_string()),
+                local_port: Some(4500),
+                local_id: Some("gw1".to_string()),
+                remote_host: Some("192.0.2.2".to_string()),
+                remote_port: Some(4500),
+                remote_id: Some("gw2".to_string()),
+                remote_xauth_id: None,
+                remote_eap_id: None,
+                initiator: true,
+                initiator_spi: "1f3c5a7e9b2d4f60".to_string(),
+                responder_spi: "8a6c4e2f0b1d3957".to_string(),
+                nat_local: false,
+                nat_remote: true,
+                nat_fake: false,
+                nat_any: true,
+                if_id_in: None,
+                if_id_out: None,
+                encr_alg: Some("AES_CBC".to_string()),
+                encr_keysize: Some(256),
+                integ_alg: Some("HMAC_SHA2_256_128".to_string()),
+                integ_keysize: None,
+                prf_alg: Some("PRF_HMAC_SHA2_256".to_string()),
+                dh_group: Some("CURVE_25519".to_string()),
+                ppk: false,
+                established: Some(120),
+                rekey_time: Some(13800),
+                reauth_time: None,
+                local_vips: vec![],
+                remote_vips: vec![],
+                tasks_queued: vec!["IKE_DPD".to_string()],
+                tasks_active: vec![],
+                tasks_passive: vec![],
+                child_sas: indexmap! {
+                    "net-net-3".to_string() => ChildSa {
+                        name: "net-net".to_string(),
+                        uniqueid: 3,
+                        reqid: 1,
+                        state: ChildSaState::Installed,
+                        mode: IpsecMode::Tunnel,
+                        protocol: IpsecProtocol::Esp,
+                        encap: true,
+                        spi_in: Some("c1a2b3d4".to_string()),
+                        spi_out: Some("c5d6e7f8".to_string()),
+                        cpi_in: None,
+                        cpi_out: None,
+                        mark_in: None,
+                        mark_mask_in: None,
+                        mark_out: None,
+                        mark_mask_out: None,
+                        if_id_in: None,
+                        if_id_out: None,
+                        encr_alg: Some("AES_GCM_16".to_string()),
+                        encr_keysize: Some(256),
+                        integ_alg: None,
+                        integ_keysize: None,
+                        prf_alg: None,
+                        dh_group: None,
+                        esn: true,
+                        bytes_in: 1024,
+                        packets_in: 8,
+                        use_in: Some(5),
+                        bytes_out: 2048,
+                        packets_out: 16,
+                        use_out: Some(4),
+                        rekey_time: Some(3300),
+                        life_time: Some(3900),
+                        install_time: Some(120),
+                        local_ts: vec!["10.1.0.0/16".to_string()],
+                        remote_ts: vec!["10.2.0.0/16".to_string()],
+                    },
+                },
+            },
+            IkeSa {
+                name: "gw-gw".to_string(),
+                uniqueid: 13,
+                version: 2,
+                state: IkeSaState::Connecting,
+                local_host: None,
+                local_port: None,
+                local_id: None,
+                remote_host: None,
+                remote_port: None,
+                remote_id: None,
+                remote_xauth_id: None,
+                remote_eap_id: None,
+                initiator: false,
+                initiator_spi: "2b4d6f8091a3c5e7".to_string(),
+                responder_spi: "0000000000000000".to_string(),
+                nat_local: false,
+                nat_remote: false,
+                nat_fake: false,
+                nat_any: false,
+                if_id_in: None,
+                if_id_out: None,
+                encr_alg: None,
+                encr_keysize: None,
+                integ_alg: None,
+                integ_keysize: None,
+                prf_alg: None,
+                dh_group: None,
+                ppk: false,
+                established: None,
+                rekey_time: None,
+                reauth_time: None,
+                local_vips: vec![],
+                remote_vips: vec![],
+                tasks_queued: vec![],
+                tasks_active: vec![],
+                tasks_passive: vec![],
+                child_sas: indexmap! {},
+            },
+        ],
+    );
+}
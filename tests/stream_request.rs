@@ -1,10 +1,11 @@
 use indexmap::{indexmap, IndexMap};
 
-use rsvici::Client;
+use rsvici::{error::Category, Client, ClientOptions, Value};
 
-use futures_util::stream::TryStreamExt;
+use futures_util::{pin_mut, poll, stream::TryStreamExt, StreamExt};
 use pretty_assertions::assert_eq;
 use serde::Deserialize;
+use tokio::task;
 use tokio_test::io::Builder;
 
 type Conns = IndexMap<String, Conn>;
@@ -116,7 +117,7 @@ async fn stream_request() {
         ])
         .build();
 
-    let mut client = Client::new(mock_stream);
+    let client = Client::new(mock_stream);
 
     let stream = client.stream_request::<(), Conns>("list-conns", "list-conn", ());
     let actual: Vec<_> = stream.try_collect().await.unwrap();
@@ -152,3 +153,498 @@ async fn stream_request() {
         ]
     );
 }
+
+#[tokio::test]
+async fn stream_request_blocks_other_commands() {
+    #[rustfmt::skip]
+    let (mock_stream, mut handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            3, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 12,
+            // packet type
+            0, 10, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 116,
+            // packet type
+            7, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+            // conn-0
+            1, 6, b'c', b'o', b'n', b'n', b'-', b'0',
+            // local_addrs
+            4, 11, b'l', b'o', b'c', b'a', b'l', b'_', b'a', b'd', b'd', b'r', b's',
+            // %any
+            5, 0, 4, b'%', b'a', b'n', b'y',
+            // local_addrs end
+            6,
+            // remote_addrs
+            4, 12, b'r', b'e', b'm', b'o', b't', b'e', b'_', b'a', b'd', b'd', b'r', b's',
+            // %any
+            5, 0, 4, b'%', b'a', b'n', b'y',
+            // remote_addrs end
+            6,
+            // version = IKEv1/2
+            3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 7, b'I', b'K', b'E', b'v', b'1', b'/', b'2',
+            // reauth_time = 0
+            3, 11, b'r', b'e', b'a', b'u', b't', b'h', b'_', b't', b'i', b'm', b'e', 0, 1, b'0',
+            // rekey_time = 14400
+            3, 10, b'r', b'e', b'k', b'e', b'y', b'_', b't', b'i', b'm', b'e', 0, 5, b'1', b'4', b'4', b'0', b'0',
+            // conn-0 end
+            2,
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    let mut stream = Box::pin(client.stream_request::<(), Conns>("list-conns", "list-conn", ()));
+    let first = stream.try_next().await.unwrap();
+
+    let reload = client.request::<(), ()>("reload-settings", ());
+    pin_mut!(reload);
+
+    assert!(poll!(&mut reload).is_pending());
+    task::yield_now().await;
+
+    #[rustfmt::skip]
+    handle
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            4, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ]);
+
+    let (rest, reload) = tokio::join!(stream.try_collect::<Vec<_>>(), reload);
+    assert_eq!(
+        first,
+        Some(indexmap! {
+            "conn-0".to_string() => Conn {
+                local_addrs: vec![
+                    "%any".to_string(),
+                ],
+                remote_addrs: vec![
+                    "%any".to_string(),
+                ],
+                version: "IKEv1/2".to_string(),
+                reauth_time: 0,
+                rekey_time: 14400,
+            },
+        })
+    );
+    assert_eq!(rest.unwrap(), Vec::<Conns>::new());
+    reload.unwrap();
+}
+
+#[tokio::test]
+async fn stream_request_tolerate_invalid_messages() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            3, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 12,
+            // packet type
+            0, 10, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 22,
+            // packet type
+            7, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+            // conn-0 = a
+            3, 6, b'c', b'o', b'n', b'n', b'-', b'0', 0, 1, b'a',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 114,
+            // packet type
+            7, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+            // conn-1
+            1, 6, b'c', b'o', b'n', b'n', b'-', b'1',
+            // local_addrs
+            4, 11, b'l', b'o', b'c', b'a', b'l', b'_', b'a', b'd', b'd', b'r', b's',
+            // %any
+            5, 0, 4, b'%', b'a', b'n', b'y',
+            // local_addrs end
+            6,
+            // remote_addrs
+            4, 12, b'r', b'e', b'm', b'o', b't', b'e', b'_', b'a', b'd', b'd', b'r', b's',
+            // %any
+            5, 0, 4, b'%', b'a', b'n', b'y',
+            // remote_addrs end
+            6,
+            // version = IKEv2
+            3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 5, b'I', b'K', b'E', b'v', b'2',
+            // reauth_time = 0
+            3, 11, b'r', b'e', b'a', b'u', b't', b'h', b'_', b't', b'i', b'm', b'e', 0, 1, b'0',
+            // rekey_time = 86400
+            3, 10, b'r', b'e', b'k', b'e', b'y', b'_', b't', b'i', b'm', b'e', 0, 5, b'8', b'6', b'4', b'0', b'0',
+            // conn-1 end
+            2,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            4, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build();
+
+    let options = ClientOptions {
+        tolerate_invalid_messages: true,
+        ..Default::default()
+    };
+    let client = Client::with_options(mock_stream, options);
+
+    let stream = client.stream_request::<(), Conns>("list-conns", "list-conn", ());
+    let actual: Vec<_> = stream.collect().await;
+    assert_eq!(actual.len(), 2);
+
+    let error = actual[0].as_ref().unwrap_err();
+    assert_eq!(error.classify(), Category::Data);
+
+    #[rustfmt::skip]
+    assert_eq!(
+        error.payload(),
+        Some(&[
+            // conn-0 = a
+            3, 6, b'c', b'o', b'n', b'n', b'-', b'0', 0, 1, b'a',
+        ][..]),
+    );
+
+    let conns = actual[1].as_ref().unwrap();
+    assert_eq!(conns.keys().collect::<Vec<_>>(), vec!["conn-1"]);
+}
+
+#[tokio::test]
+async fn stream_request_dropped() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            3, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 12,
+            // packet type
+            0, 10, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 22,
+            // packet type
+            7, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+            // conn-0 = a
+            3, 6, b'c', b'o', b'n', b'n', b'-', b'0', 0, 1, b'a',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            4, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let mut stream = Box::pin(client.stream_request::<(), Value>("list-conns", "list-conn", ()));
+    let actual = stream.try_next().await.unwrap().unwrap();
+    assert_eq!(actual["conn-0"].as_str(), Some("a"));
+
+    // The event is unregistered before the response arrives.
+    drop(stream);
+
+    client.request::<(), ()>("reload-settings", ()).await.unwrap();
+}
+
+#[tokio::test]
+async fn stream_request_frame_too_large() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            3, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 12,
+            // packet type
+            0, 10, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 22,
+            // packet type
+            7, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+            // conn-0 = a
+            3, 6, b'c', b'o', b'n', b'n', b'-', b'0', 0, 1, b'a',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            4, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .build();
+
+    let client = Client::with_options(
+        mock_stream,
+        ClientOptions {
+            max_frame_size: 16,
+            ..Default::default()
+        },
+    );
+
+    // The oversized event fails the stream rather than leaving out an item.
+    let mut stream = Box::pin(client.stream_request::<(), Value>("list-conns", "list-conn", ()));
+    let actual = stream.try_next().await.unwrap_err();
+    assert_eq!(actual.classify(), Category::Protocol);
+    drop(stream);
+
+    client.request::<(), ()>("reload-settings", ()).await.unwrap();
+}
+
+#[tokio::test]
+async fn stream_request_dropped_before_confirmation() {
+    #[rustfmt::skip]
+    let (mock_stream, mut handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            3, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    let mut stream = Box::pin(client.stream_request::<(), Value>("list-conns", "list-conn", ()));
+    assert!(poll!(stream.next()).is_pending());
+    task::yield_now().await;
+
+    #[rustfmt::skip]
+    handle
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            4, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ]);
+
+    // The confirmation has been received but not consumed yet.
+    drop(stream);
+    task::yield_now().await;
+    task::yield_now().await;
+
+    client.request::<(), ()>("reload-settings", ()).await.unwrap();
+}
+
+#[tokio::test]
+async fn stream_request_unregister_failure() {
+    #[rustfmt::skip]
+    let (mock_stream, _handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            3, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 12,
+            // packet type
+            0, 10, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 22,
+            // packet type
+            7, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+            // conn-0 = a
+            3, 6, b'c', b'o', b'n', b'n', b'-', b'0', 0, 1, b'a',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 11,
+            // packet type
+            4, 9, b'l', b'i', b's', b't', b'-', b'c', b'o', b'n', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            6,
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+    let mut errors = client.listen_for_errors();
+
+    let stream = client.stream_request::<(), Conns>("list-conns", "list-conn", ());
+    let actual: Vec<_> = stream.collect().await;
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual[0].as_ref().unwrap_err().classify(), Category::Data);
+
+    let actual = errors.next().await.unwrap();
+    assert_eq!(actual.classify(), Category::UnknownEvent);
+}
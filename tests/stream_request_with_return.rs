@@ -1,4 +1,4 @@
-use rsvici::{Client, Error};
+use rsvici::{Client, Error, StreamResponse};
 
 use futures_util::{pin_mut, stream::TryStreamExt};
 use pretty_assertions::assert_eq;
@@ -102,7 +102,7 @@ async fn stream_request_with_success() {
         ])
         .build();
 
-    let mut client = Client::new(mock_stream);
+    let client = Client::new(mock_stream);
     let initiate = Initiate {
         ike: "gw-gw".to_string(),
         child: "net-net".to_string(),
@@ -197,7 +197,7 @@ async fn stream_request_with_failure() {
         ])
         .build();
 
-    let mut client = Client::new(mock_stream);
+    let client = Client::new(mock_stream);
     let initiate = Initiate {
         ike: "gw-gw".to_string(),
         child: "net-net".to_string(),
@@ -297,7 +297,7 @@ async fn stream_request_with_failure_no_errmsg() {
         ])
         .build();
 
-    let mut client = Client::new(mock_stream);
+    let client = Client::new(mock_stream);
     let initiate = Initiate {
         ike: "gw-gw".to_string(),
         child: "net-net".to_string(),
@@ -332,3 +332,101 @@ async fn stream_request_with_failure_no_errmsg() {
     );
     assert_eq!(err.map(|e| e.to_string()), Some("command failed".to_string()));
 }
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+struct Log {
+    group: String,
+    level: u32,
+    msg: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Terminate {
+    ike: String,
+}
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+struct Terminated {
+    matches: u32,
+    terminated: u32,
+}
+
+#[tokio::test]
+async fn stream_request_with_response() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            3, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 23,
+            // packet type
+            0, 9, b't', b'e', b'r', b'm', b'i', b'n', b'a', b't', b'e',
+            // ike = gw-gw
+            3, 3, b'i', b'k', b'e', 0, 5, b'g', b'w', b'-', b'g', b'w',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 43,
+            // packet type
+            7, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 42,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+            // matches = 1
+            3, 7, b'm', b'a', b't', b'c', b'h', b'e', b's', 0, 1, b'1',
+            // terminated = 1
+            3, 10, b't', b'e', b'r', b'm', b'i', b'n', b'a', b't', b'e', b'd', 0, 1, b'1',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            4, 11, b'c', b'o', b'n', b't', b'r', b'o', b'l', b'-', b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+    let terminate = Terminate { ike: "gw-gw".to_string() };
+
+    let stream = client.stream_request_with_response::<Terminate, Log, Terminated>("terminate", "control-log", terminate);
+    let actual: Vec<_> = stream.try_collect().await.unwrap();
+    assert_eq!(
+        actual,
+        vec![
+            StreamResponse::Event(Log {
+                group: "IKE".to_string(),
+                level: 1,
+                msg: "a".to_string(),
+            }),
+            StreamResponse::Response(Terminated { matches: 1, terminated: 1 }),
+        ]
+    );
+}
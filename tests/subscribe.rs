@@ -1,8 +1,11 @@
-use rsvici::Client;
+use std::time::SystemTime;
 
-use futures_util::{stream::TryStreamExt, StreamExt};
+use rsvici::{error::Category, vici, Buffer, Client, ClientOptions, Value};
+
+use futures_util::{poll, stream::TryStreamExt, StreamExt};
 use pretty_assertions::assert_eq;
 use serde::Deserialize;
+use tokio::task;
 use tokio_test::io::Builder;
 
 #[derive(Debug, Deserialize, Eq, PartialEq)]
@@ -54,7 +57,7 @@ async fn subscribe() {
         ])
         .build_with_handle();
 
-    let mut client = Client::new(mock_stream);
+    let client = Client::new(mock_stream);
 
     {
         let stream = client.subscribe::<Log>("log");
@@ -103,3 +106,721 @@ async fn subscribe() {
             5,
         ]);
 }
+
+#[tokio::test]
+async fn subscribe_multiple() {
+    #[rustfmt::skip]
+    let (mock_stream, mut handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    let mut first = Box::pin(client.subscribe::<Log>("log"));
+    let mut second = Box::pin(client.subscribe::<Log>("log"));
+
+    assert!(poll!(first.next()).is_pending());
+    assert!(poll!(second.next()).is_pending());
+    task::yield_now().await;
+
+    #[rustfmt::skip]
+    handle
+        .read(&[
+            // header
+            0, 0, 0, 66,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = received FRAGMENTATION vendor ID
+            3, 3, b'm', b's', b'g', 0, 32, b'r', b'e', b'c', b'e', b'i', b'v', b'e', b'd', b' ', b'F', b'R', b'A', b'G', b'M', b'E', b'N', b'T', b'A', b'T', b'I', b'O', b'N', b' ', b'v', b'e', b'n', b'd', b'o', b'r', b' ', b'I', b'D',
+        ]);
+
+    let expected = Log {
+        group: "IKE".to_string(),
+        level: 1,
+        msg: "received FRAGMENTATION vendor ID".to_string(),
+    };
+    assert_eq!(first.try_next().await.unwrap(), Some(expected));
+
+    let expected = Log {
+        group: "IKE".to_string(),
+        level: 1,
+        msg: "received FRAGMENTATION vendor ID".to_string(),
+    };
+    assert_eq!(second.try_next().await.unwrap(), Some(expected));
+
+    drop(first);
+    task::yield_now().await;
+
+    #[rustfmt::skip]
+    handle
+        .read(&[
+            // header
+            0, 0, 0, 56,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = received DPD vendor ID
+            3, 3, b'm', b's', b'g', 0, 22, b'r', b'e', b'c', b'e', b'i', b'v', b'e', b'd', b' ', b'D', b'P', b'D', b' ', b'v', b'e', b'n', b'd', b'o', b'r', b' ', b'I', b'D',
+        ]);
+
+    let expected = Log {
+        group: "IKE".to_string(),
+        level: 1,
+        msg: "received DPD vendor ID".to_string(),
+    };
+    assert_eq!(second.try_next().await.unwrap(), Some(expected));
+
+    drop(second);
+
+    #[rustfmt::skip]
+    handle
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            4, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ]);
+}
+
+#[tokio::test]
+async fn subscribe_dropped_before_confirmation() {
+    #[rustfmt::skip]
+    let (mock_stream, mut handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    let mut stream = Box::pin(client.subscribe::<Log>("log"));
+    assert!(poll!(stream.next()).is_pending());
+    task::yield_now().await;
+
+    #[rustfmt::skip]
+    handle
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            4, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ]);
+
+    // The confirmation has been received but not consumed yet.
+    drop(stream);
+    task::yield_now().await;
+    task::yield_now().await;
+
+    client.request::<(), ()>("reload-settings", ()).await.unwrap();
+}
+
+#[tokio::test]
+async fn subscribe_with_request_while_reading() {
+    #[rustfmt::skip]
+    let (mock_stream, mut handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 56,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    let mut stream = Box::pin(client.subscribe::<Log>("log"));
+    assert!(poll!(stream.next()).is_pending());
+    task::yield_now().await;
+
+    #[rustfmt::skip]
+    handle
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = received DPD vendor ID
+            3, 3, b'm', b's', b'g', 0, 22, b'r', b'e', b'c', b'e', b'i', b'v', b'e', b'd', b' ', b'D', b'P', b'D', b' ', b'v', b'e', b'n', b'd', b'o', b'r', b' ', b'I', b'D',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ]);
+
+    let (actual, reload) = tokio::join!(stream.try_next(), client.request::<(), ()>("reload-settings", ()));
+    assert_eq!(
+        actual.unwrap(),
+        Some(Log {
+            group: "IKE".to_string(),
+            level: 1,
+            msg: "received DPD vendor ID".to_string(),
+        })
+    );
+    reload.unwrap();
+}
+
+#[tokio::test]
+async fn subscribe_with_buffer_drop_oldest() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 35,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 35,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = b
+            3, 3, b'm', b's', b'g', 0, 1, b'b',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 35,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = c
+            3, 3, b'm', b's', b'g', 0, 1, b'c',
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let mut stream = Box::pin(client.subscribe_with_buffer::<Log>("log", Buffer::DropOldest(1)));
+    assert!(poll!(stream.next()).is_pending());
+    task::yield_now().await;
+
+    // The listener does not wait for the subscriber to consume its messages.
+    client.request::<(), ()>("reload-settings", ()).await.unwrap();
+
+    let actual = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(actual.classify(), Category::Lagged);
+    assert_eq!(actual.lost_messages(), Some(2));
+
+    let actual = stream.next().await.unwrap().unwrap();
+    assert_eq!(
+        actual,
+        Log {
+            group: "IKE".to_string(),
+            level: 1,
+            msg: "c".to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn subscribe_tolerate_invalid_messages() {
+    #[rustfmt::skip]
+    let (mock_stream, _handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 35,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = x
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'x',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 35,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = 1
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'1',
+            // msg = b
+            3, 3, b'm', b's', b'g', 0, 1, b'b',
+        ])
+        .build_with_handle();
+
+    let options = ClientOptions {
+        tolerate_invalid_messages: true,
+        ..Default::default()
+    };
+    let client = Client::with_options(mock_stream, options);
+
+    let mut stream = Box::pin(client.subscribe::<Log>("log"));
+
+    let actual = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(actual.classify(), Category::Data);
+
+    #[rustfmt::skip]
+    assert_eq!(
+        actual.payload(),
+        Some(&[
+            // group = IKE
+            3, 5, b'g', b'r', b'o', b'u', b'p', 0, 3, b'I', b'K', b'E',
+            // level = x
+            3, 5, b'l', b'e', b'v', b'e', b'l', 0, 1, b'x',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+        ][..]),
+    );
+
+    let actual = stream.next().await.unwrap().unwrap();
+    assert_eq!(
+        actual,
+        Log {
+            group: "IKE".to_string(),
+            level: 1,
+            msg: "b".to_string(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn subscribe_frame_too_large() {
+    #[rustfmt::skip]
+    let (mock_stream, _handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = abcde
+            3, 3, b'm', b's', b'g', 0, 5, b'a', b'b', b'c', b'd', b'e',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = f
+            3, 3, b'm', b's', b'g', 0, 1, b'f',
+        ])
+        .build_with_handle();
+
+    let client = Client::with_options(
+        mock_stream,
+        ClientOptions {
+            max_frame_size: 16,
+            ..Default::default()
+        },
+    );
+
+    // The subscription goes on after the oversized message.
+    let stream = client.subscribe::<Value>("log");
+    let actual: Vec<_> = stream.take(2).collect().await;
+    assert_eq!(actual[0].as_ref().unwrap_err().classify(), Category::Protocol);
+    assert_eq!(actual[1].as_ref().unwrap(), &vici! { msg: "f" });
+}
+
+#[tokio::test]
+async fn subscribe_many() {
+    #[rustfmt::skip]
+    let (mock_stream, mut handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 12,
+            // packet type
+            3, 10, b'i', b'k', b'e', b'-', b'u', b'p', b'd', b'o', b'w', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 21,
+            // packet type
+            7, 10, b'i', b'k', b'e', b'-', b'u', b'p', b'd', b'o', b'w', b'n',
+            // up = yes
+            3, 2, b'u', b'p', 0, 3, b'y', b'e', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = b
+            3, 3, b'm', b's', b'g', 0, 1, b'b',
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    {
+        let stream = client.subscribe_many::<Value>(&["log", "ike-updown", "log"]);
+        let actual: Vec<_> = stream.take(3).try_collect().await.unwrap();
+        assert_eq!(
+            actual,
+            vec![
+                ("log".to_string(), vici! { msg: "a" }),
+                ("ike-updown".to_string(), vici! { up: "yes" }),
+                ("log".to_string(), vici! { msg: "b" }),
+            ]
+        );
+    }
+
+    #[rustfmt::skip]
+    handle
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            4, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 12,
+            // packet type
+            4, 10, b'i', b'k', b'e', b'-', b'u', b'p', b'd', b'o', b'w', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ]);
+}
+
+#[tokio::test]
+async fn subscribe_many_unknown_event() {
+    #[rustfmt::skip]
+    let (mock_stream, mut handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 9,
+            // packet type
+            3, 7, b'u', b'n', b'k', b'n', b'o', b'w', b'n',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            6,
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    {
+        let stream = client.subscribe_many::<Value>(&["log", "unknown"]);
+        let actual: Vec<_> = stream.collect().await;
+        assert_eq!(actual.len(), 1);
+        assert!(actual[0].as_ref().unwrap_err().is_unknown_event());
+    }
+
+    // Only the event that has been confirmed is unregistered.
+    #[rustfmt::skip]
+    handle
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            4, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ]);
+}
+
+#[tokio::test]
+async fn subscribe_many_dropped_before_confirmation() {
+    #[rustfmt::skip]
+    let (mock_stream, mut handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    let mut stream = Box::pin(client.subscribe_many::<Value>(&["log", "ike-updown"]));
+    assert!(poll!(stream.next()).is_pending());
+    task::yield_now().await;
+
+    #[rustfmt::skip]
+    handle
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            4, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .write(&[
+            // header
+            0, 0, 0, 17,
+            // packet type
+            0, 15, b'r', b'e', b'l', b'o', b'a', b'd', b'-', b's', b'e', b't', b't', b'i', b'n', b'g', b's',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            1,
+        ]);
+
+    // The confirmation has been received but not consumed yet.
+    drop(stream);
+    task::yield_now().await;
+    task::yield_now().await;
+
+    client.request::<(), ()>("reload-settings", ()).await.unwrap();
+}
+
+#[tokio::test]
+async fn subscribe_many_empty() {
+    let (mock_stream, _handle) = Builder::new().build_with_handle();
+    let client = Client::new(mock_stream);
+
+    let stream = client.subscribe_many::<Value>(&[]);
+    let actual: Vec<_> = stream.collect().await;
+    assert!(actual.is_empty());
+}
+
+#[tokio::test]
+async fn subscribe_events() {
+    #[rustfmt::skip]
+    let (mock_stream, _handle) = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 5,
+            // packet type
+            3, 3, b'l', b'o', b'g',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 1,
+            // packet type
+            5,
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 13,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = a
+            3, 3, b'm', b's', b'g', 0, 1, b'a',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 14,
+            // packet type
+            7, 3, b'l', b'o', b'g',
+            // msg = bc
+            3, 3, b'm', b's', b'g', 0, 2, b'b', b'c',
+        ])
+        .build_with_handle();
+
+    let client = Client::new(mock_stream);
+
+    let before = SystemTime::now();
+    let stream = client.subscribe_events::<Value>("log");
+    let actual: Vec<_> = stream.take(2).try_collect().await.unwrap();
+    let after = SystemTime::now();
+
+    assert_eq!(actual[0].name(), "log");
+    assert_eq!(actual[0].sequence(), 0);
+    assert_eq!(actual[0].size(), 8);
+    assert_eq!(actual[0].message(), &vici! { msg: "a" });
+
+    assert_eq!(actual[1].name(), "log");
+    assert_eq!(actual[1].sequence(), 1);
+    assert_eq!(actual[1].size(), 9);
+    assert_eq!(actual[1].clone().into_message(), vici! { msg: "bc" });
+
+    for event in &actual {
+        assert!((before..=after).contains(&event.received()));
+    }
+    assert!(actual[0].received() <= actual[1].received());
+}
@@ -0,0 +1,142 @@
+use indexmap::indexmap;
+
+use rsvici::{vici, Client, Value};
+
+use pretty_assertions::assert_eq;
+use tokio_test::io::Builder;
+
+#[rustfmt::skip]
+const CONN: &[u8] = &[
+    // conn-0
+    1, 6, b'c', b'o', b'n', b'n', b'-', b'0',
+    // local_addrs
+    4, 11, b'l', b'o', b'c', b'a', b'l', b'_', b'a', b'd', b'd', b'r', b's',
+    // 192.0.2.1
+    5, 0, 9, b'1', b'9', b'2', b'.', b'0', b'.', b'2', b'.', b'1',
+    // local_addrs end
+    6,
+    // version = IKEv2
+    3, 7, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0, 5, b'I', b'K', b'E', b'v', b'2',
+    // conn-0 end
+    2,
+    // id = 0xff
+    3, 2, b'i', b'd', 0, 1, 0xff,
+];
+
+#[test]
+fn value() {
+    let actual: Value = serde_vici::from_slice(CONN).unwrap();
+    assert_eq!(
+        actual,
+        Value::Section(indexmap! {
+            "conn-0".to_string() => Value::Section(indexmap! {
+                "local_addrs".to_string() => Value::List(vec![Value::String("192.0.2.1".to_string())]),
+                "version".to_string() => Value::String("IKEv2".to_string()),
+            }),
+            "id".to_string() => Value::Bytes(vec![0xff]),
+        }),
+    );
+
+    assert_eq!(actual["conn-0"]["local_addrs"][0].as_str(), Some("192.0.2.1"));
+    assert_eq!(actual["conn-0"]["version"].as_str(), Some("IKEv2"));
+    assert_eq!(actual["id"].as_bytes(), Some(&[0xff][..]));
+    assert_eq!(actual.get("conn-1"), None);
+    assert_eq!(actual["conn-0"].get(0), None);
+
+    assert_eq!(serde_vici::to_vec(&actual).unwrap(), CONN);
+}
+
+#[tokio::test]
+async fn request_value() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 22,
+            // packet type
+            0, 8, b'i', b'n', b'i', b't', b'i', b'a', b't', b'e',
+            // child = net
+            3, 5, b'c', b'h', b'i', b'l', b'd', 0, 3, b'n', b'e', b't',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 15,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let message = Value::Section(indexmap! {
+        "child".to_string() => Value::from("net"),
+    });
+    let actual: Value = client.request("initiate", message).await.unwrap();
+    assert_eq!(actual["success"].as_str(), Some("yes"));
+}
+
+#[test]
+fn vici_macro() {
+    let child = "net-net".to_string();
+    let actual = vici! {
+        ike: "gw-gw",
+        child: &child,
+        timeout: 5000,
+        "init-limits": false,
+        opts: {
+            local_addrs: ["192.0.2.1", "192.0.2.2"],
+            empty: {},
+        },
+        list: [],
+    };
+
+    assert_eq!(
+        actual,
+        Value::Section(indexmap! {
+            "ike".to_string() => Value::String("gw-gw".to_string()),
+            "child".to_string() => Value::String("net-net".to_string()),
+            "timeout".to_string() => Value::String("5000".to_string()),
+            "init-limits".to_string() => Value::String("no".to_string()),
+            "opts".to_string() => Value::Section(indexmap! {
+                "local_addrs".to_string() => Value::List(vec![
+                    Value::String("192.0.2.1".to_string()),
+                    Value::String("192.0.2.2".to_string()),
+                ]),
+                "empty".to_string() => Value::Section(indexmap! {}),
+            }),
+            "list".to_string() => Value::List(vec![]),
+        }),
+    );
+}
+
+#[tokio::test]
+async fn request_vici_macro() {
+    #[rustfmt::skip]
+    let mock_stream = Builder::new()
+        .write(&[
+            // header
+            0, 0, 0, 38,
+            // packet type
+            0, 8, b'i', b'n', b'i', b't', b'i', b'a', b't', b'e',
+            // ike = gw-gw
+            3, 3, b'i', b'k', b'e', 0, 5, b'g', b'w', b'-', b'g', b'w',
+            // child = net-net
+            3, 5, b'c', b'h', b'i', b'l', b'd', 0, 7, b'n', b'e', b't', b'-', b'n', b'e', b't',
+        ])
+        .read(&[
+            // header
+            0, 0, 0, 15,
+            // packet type
+            1,
+            // success = yes
+            3, 7, b's', b'u', b'c', b'c', b'e', b's', b's', 0, 3, b'y', b'e', b's',
+        ])
+        .build();
+
+    let client = Client::new(mock_stream);
+
+    let actual: Value = client.request("initiate", vici! { ike: "gw-gw", child: "net-net" }).await.unwrap();
+    assert_eq!(actual["success"].as_str(), Some("yes"));
+}